@@ -2,6 +2,18 @@ use std::env;
 use std::fs;
 use std::path::Path;
 
+/// Metadata derived for each wreq-util emulation profile
+struct Profile {
+    variant: String,
+    name: String,
+    family: String,
+    group: String,
+    version: String,
+    platform: &'static str,
+    private: bool,
+    engine: &'static str,
+}
+
 fn main() {
     // Dynamically extract all browser profiles from wreq-util by reading the source
     let profiles = extract_profiles_from_source();
@@ -11,7 +23,7 @@ fn main() {
     // Generate TypeScript type definition
     let ts_type = generate_typescript_type(&profiles);
 
-    // Generate Rust profiles array
+    // Generate Rust profiles table
     let rust_profiles = generate_rust_profiles(&profiles);
 
     // Write to src directory (going up one level from rust/)
@@ -21,42 +33,113 @@ fn main() {
     let ts_dest = Path::new(&manifest_dir).parent().unwrap().join("src").join("generated-types.ts");
     fs::write(&ts_dest, ts_type).unwrap();
 
-    // Write Rust profiles array
+    // Write Rust profiles table
     let rust_dest = Path::new(&manifest_dir).join("src").join("generated_profiles.rs");
     fs::write(&rust_dest, rust_profiles).unwrap();
 
     println!("cargo:rerun-if-changed=build.rs");
 }
 
-fn generate_typescript_type(profiles: &[String]) -> String {
+fn push_union(ts_content: &mut String, name: &str, values: &[String]) {
+    ts_content.push_str(&format!("export type {} =\n", name));
+
+    for (i, value) in values.iter().enumerate() {
+        if i == values.len() - 1 {
+            // Last value - put semicolon on same line
+            ts_content.push_str(&format!("  | '{}';\n", value));
+        } else {
+            ts_content.push_str(&format!("  | '{}'\n", value));
+        }
+    }
+}
+
+fn unique<'a>(values: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::new();
+    for value in values {
+        if !unique.iter().any(|v| v == value) {
+            unique.push(value.to_string());
+        }
+    }
+    unique
+}
+
+fn generate_typescript_type(profiles: &[Profile]) -> String {
     let mut ts_content = String::from(
         "/**\n * Auto-generated from Rust build script\n * DO NOT EDIT MANUALLY\n */\n\n"
     );
 
+    let names: Vec<String> = profiles.iter().map(|p| p.name.clone()).collect();
     ts_content.push_str("/**\n * Browser profile names supported\n */\n");
-    ts_content.push_str("export type BrowserProfile =\n");
+    push_union(&mut ts_content, "BrowserProfile", &names);
 
-    for (i, profile) in profiles.iter().enumerate() {
-        if i == profiles.len() - 1 {
-            // Last profile - put semicolon on same line
-            ts_content.push_str(&format!("  | '{}';\n", profile));
-        } else {
-            ts_content.push_str(&format!("  | '{}'\n", profile));
-        }
-    }
+    let families = unique(profiles.iter().map(|p| p.family.as_str()));
+    ts_content.push_str("\n/**\n * Browser families the profiles belong to\n */\n");
+    push_union(&mut ts_content, "BrowserFamily", &families);
+
+    let aliases: Vec<String> = unique(profiles.iter().map(|p| p.group.as_str()))
+        .into_iter()
+        .map(|group| format!("{}_latest", group))
+        .collect();
+    ts_content.push_str("\n/**\n * Aliases resolving to the newest profile of a browser line\n */\n");
+    push_union(&mut ts_content, "ProfileAlias", &aliases);
+
+    let platforms = unique(profiles.iter().map(|p| p.platform));
+    ts_content.push_str("\n/**\n * Device class a profile emulates\n */\n");
+    push_union(&mut ts_content, "ProfilePlatform", &platforms);
+
+    let engines = unique(profiles.iter().map(|p| p.engine));
+    ts_content.push_str("\n/**\n * Rendering/network engine behind a profile\n */\n");
+    push_union(&mut ts_content, "BrowserEngine", &engines);
 
     ts_content
 }
 
-fn generate_rust_profiles(profiles: &[String]) -> String {
+fn generate_rust_profiles(profiles: &[Profile]) -> String {
     let mut rust_content = String::from(
         "// Auto-generated from build script\n// DO NOT EDIT MANUALLY\n\n"
     );
 
-    rust_content.push_str("pub const BROWSER_PROFILES: &[&str] = &[\n");
+    rust_content.push_str("use wreq_util::Emulation;\n\n");
+
+    rust_content.push_str(
+        "#[derive(Debug, Clone, Copy, PartialEq, Eq)]\n\
+         pub enum Platform {\n    Desktop,\n    Mobile,\n    Ipad,\n}\n\n\
+         impl Platform {\n    pub fn as_str(&self) -> &'static str {\n        match self {\n            \
+         Platform::Desktop => \"desktop\",\n            Platform::Mobile => \"mobile\",\n            \
+         Platform::Ipad => \"ipad\",\n        }\n    }\n}\n\n"
+    );
+
+    rust_content.push_str(
+        "#[derive(Debug, Clone, Copy, PartialEq, Eq)]\n\
+         pub enum Engine {\n    Blink,\n    Gecko,\n    Webkit,\n    Okhttp,\n}\n\n\
+         impl Engine {\n    pub fn as_str(&self) -> &'static str {\n        match self {\n            \
+         Engine::Blink => \"blink\",\n            Engine::Gecko => \"gecko\",\n            \
+         Engine::Webkit => \"webkit\",\n            Engine::Okhttp => \"okhttp\",\n        }\n    }\n}\n\n"
+    );
+
+    rust_content.push_str(
+        "#[derive(Debug, Clone, Copy)]\n\
+         pub struct ProfileInfo {\n    pub name: &'static str,\n    pub emulation: Emulation,\n    \
+         pub family: &'static str,\n    pub group: &'static str,\n    pub version: &'static str,\n    pub platform: Platform,\n    \
+         pub private: bool,\n    pub engine: Engine,\n}\n\n"
+    );
+
+    rust_content.push_str("pub const BROWSER_PROFILES: &[ProfileInfo] = &[\n");
 
     for profile in profiles {
-        rust_content.push_str(&format!("    \"{}\",\n", profile));
+        rust_content.push_str(&format!(
+            "    ProfileInfo {{\n        name: \"{}\",\n        emulation: Emulation::{},\n        \
+             family: \"{}\",\n        group: \"{}\",\n        version: \"{}\",\n        platform: Platform::{},\n        \
+             private: {},\n        engine: Engine::{},\n    }},\n",
+            profile.name,
+            profile.variant,
+            profile.family,
+            profile.group,
+            profile.version,
+            capitalize(profile.platform),
+            profile.private,
+            capitalize(profile.engine),
+        ));
     }
 
     rust_content.push_str("];\n");
@@ -64,7 +147,68 @@ fn generate_rust_profiles(profiles: &[String]) -> String {
     rust_content
 }
 
-fn extract_profiles_from_source() -> Vec<String> {
+fn capitalize(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+// Derive family/version/platform/private/engine from a wreq-util profile name,
+// e.g. "safari_ios_17.2", "firefox_private_135", "okhttp_3.9". Names this doesn't
+// understand are left out with a warning, so a wreq-util update can't break the build.
+fn classify_profile(variant: &str, name: &str) -> Option<Profile> {
+    let (prefix, version) = match name.rsplit_once('_') {
+        Some((prefix, version)) if version.starts_with(|c: char| c.is_ascii_digit()) => (prefix, version),
+        _ => {
+            println!("cargo:warning=Skipping profile '{}': it has no version suffix", name);
+            return None;
+        }
+    };
+
+    let mut segments = prefix.split('_');
+    let family = segments.next().unwrap().to_string();
+
+    let mut platform = match family.as_str() {
+        "okhttp" => "mobile",
+        _ => "desktop",
+    };
+    let mut private = false;
+
+    for modifier in segments {
+        match modifier {
+            "ios" | "android" => platform = "mobile",
+            "ipad" => platform = "ipad",
+            "private" => private = true,
+            other => println!("cargo:warning=Ignoring unknown modifier '{}' in profile '{}'", other, name),
+        }
+    }
+
+    let engine = match family.as_str() {
+        "chrome" | "edge" | "opera" => "blink",
+        "firefox" => "gecko",
+        "safari" => "webkit",
+        "okhttp" => "okhttp",
+        other => {
+            println!("cargo:warning=Skipping profile '{}': unknown browser family '{}'", name, other);
+            return None;
+        }
+    };
+
+    Some(Profile {
+        variant: variant.to_string(),
+        name: name.to_string(),
+        family,
+        group: prefix.to_string(),
+        version: version.to_string(),
+        platform,
+        private,
+        engine,
+    })
+}
+
+fn extract_profiles_from_source() -> Vec<Profile> {
     // Find wreq-util in cargo metadata
     let metadata = std::process::Command::new("cargo")
         .args(&["metadata", "--format-version", "1"])
@@ -96,16 +240,17 @@ fn extract_profiles_from_source() -> Vec<String> {
     let content = fs::read_to_string(&emulation_file)
         .expect("Failed to read wreq-util emulation/mod.rs");
 
-    // Extract serde rename values from the file
-    // Look for patterns like: => ("profile_name", ...)
+    // Extract enum variants and serde rename values from the file
+    // Look for patterns like: Variant => ("profile_name", ...)
     let mut profiles = Vec::new();
 
     for line in content.lines() {
         // Match lines like: Chrome100 => ("chrome_100", v100::emulation),
         if let Some(start) = line.find("=> (\"") {
             if let Some(end) = line[start + 5..].find('"') {
-                let profile = &line[start + 5..start + 5 + end];
-                profiles.push(profile.to_string());
+                let variant = line[..start].trim();
+                let name = &line[start + 5..start + 5 + end];
+                profiles.extend(classify_profile(variant, name));
             }
         }
     }
@@ -0,0 +1,359 @@
+use crate::client::Response;
+use crate::errors::{ErrorCode, Failure, Phase};
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use neon::prelude::Finalize;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, RwLock};
+
+// Used by every request and WebSocket, through a session or not, that has no cassette of its own
+static GLOBAL_CASSETTE: Lazy<RwLock<Option<Arc<Cassette>>>> = Lazy::new(|| RwLock::new(None));
+
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Requests go out as usual and what they got is kept
+    Record,
+    /// Recorded interactions answer instead, and nothing is sent
+    Replay,
+}
+
+impl CassetteMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "record" => Some(Self::Record),
+            "replay" => Some(Self::Replay),
+            _ => None,
+        }
+    }
+}
+
+/// How a request is matched against the recorded ones. The method and URL always have to match.
+#[derive(Debug, Clone)]
+pub struct MatchRules {
+    /// Compare request bodies too
+    pub body: bool,
+    /// Query parameters left out of both URLs before they are compared, e.g. cache busters
+    pub ignore_query_params: Vec<String>,
+    /// Let an interaction answer more than once; otherwise each one is played once, in order
+    pub allow_repeats: bool,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        Self {
+            body: true,
+            ignore_query_params: Vec::new(),
+            allow_repeats: false,
+        }
+    }
+}
+
+/// HTTP exchanges and WebSocket conversations, recorded to be replayed without a network
+#[derive(Debug)]
+pub struct Cassette {
+    mode: CassetteMode,
+    rules: MatchRules,
+    tape: Mutex<Tape>,
+}
+
+impl Finalize for Cassette {}
+
+#[derive(Debug)]
+struct Tape {
+    interactions: Vec<Interaction>,
+    // Replay only: whether each interaction has answered yet
+    played: Vec<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CassetteFile {
+    version: u32,
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Interaction {
+    Http {
+        request: RecordedRequest,
+        response: RecordedResponse,
+    },
+    WebSocket {
+        url: String,
+        frames: Vec<Frame>,
+    },
+}
+
+/// The parts of a request that matching looks at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    method: String,
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+}
+
+impl RecordedRequest {
+    pub fn new(method: &str, url: &str, body: Option<&str>) -> Self {
+        Self {
+            method: match method {
+                "" => "GET".to_string(),
+                method => method.to_uppercase(),
+            },
+            url: url.to_string(),
+            // No body and an empty one are sent the same way
+            body: body.filter(|body| !body.is_empty()).map(str::to_string),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    url: String,
+    headers: IndexMap<String, String>,
+    #[serde(default)]
+    cookies: IndexMap<String, String>,
+    body: String,
+}
+
+/// A WebSocket message, in the order it was sent or received
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Frame {
+    Send {
+        data: Payload,
+    },
+    Receive {
+        data: Payload,
+    },
+    /// The server closed the connection
+    Close,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Payload {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl Payload {
+    fn describe(&self) -> String {
+        match self {
+            Self::Text(text) => format!("text {:?}", text),
+            Self::Binary(data) => format!("{} binary bytes", data.len()),
+        }
+    }
+}
+
+impl Cassette {
+    /// Start empty to record, or from a saved cassette to replay it
+    pub fn new(mode: CassetteMode, rules: MatchRules, contents: Option<&str>) -> Result<Self> {
+        let interactions = match contents {
+            Some(contents) => {
+                let file: CassetteFile =
+                    serde_json::from_str(contents).context("Invalid cassette file")?;
+                if file.version != FORMAT_VERSION {
+                    anyhow::bail!("Unsupported cassette version {}", file.version);
+                }
+                file.interactions
+            }
+            None if mode == CassetteMode::Replay => {
+                anyhow::bail!("A cassette needs recorded interactions to replay")
+            }
+            None => Vec::new(),
+        };
+        let played = vec![false; interactions.len()];
+        Ok(Self {
+            mode,
+            rules,
+            tape: Mutex::new(Tape {
+                interactions,
+                played,
+            }),
+        })
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub fn interaction_count(&self) -> usize {
+        self.tape.lock().unwrap().interactions.len()
+    }
+
+    /// Answer a request with the first recorded response it matches. Nothing that would
+    /// reach the network is ever returned, so a request with no match is an error.
+    pub fn replay(&self, request: &RecordedRequest) -> Result<Response> {
+        let mut tape = self.tape.lock().unwrap();
+        let mut already_played = false;
+        let mut found = None;
+        for (index, interaction) in tape.interactions.iter().enumerate() {
+            let Interaction::Http {
+                request: recorded,
+                response,
+            } = interaction
+            else {
+                continue;
+            };
+            if !self.matches(recorded, request) {
+                continue;
+            }
+            if tape.played[index] && !self.rules.allow_repeats {
+                already_played = true;
+                continue;
+            }
+            found = Some((index, response.clone()));
+            break;
+        }
+
+        let Some((index, response)) = found else {
+            let hint = if already_played {
+                "; the ones that match were already played (see allowRepeats)"
+            } else {
+                ""
+            };
+            let message = format!(
+                "Cassette has no recorded response for {} {}{}",
+                request.method, request.url, hint
+            );
+            return Err(Failure::new(ErrorCode::Cassette, Phase::Setup, message).into());
+        };
+        tape.played[index] = true;
+
+        Ok(Response {
+            status: response.status,
+            headers: response.headers,
+            body: response.body,
+            cookies: response.cookies,
+            url: response.url,
+            proxy: None,
+            cache_status: None,
+        })
+    }
+
+    pub fn record(&self, request: RecordedRequest, response: &Response) {
+        let response = RecordedResponse {
+            status: response.status,
+            url: response.url.clone(),
+            headers: response.headers.clone(),
+            cookies: response.cookies.clone(),
+            body: response.body.clone(),
+        };
+        self.push(Interaction::Http { request, response });
+    }
+
+    /// The messages recorded for a WebSocket to `url`, to be played back in order
+    pub fn replay_websocket(&self, url: &str) -> Result<VecDeque<Frame>> {
+        let mut tape = self.tape.lock().unwrap();
+        let found = tape
+            .interactions
+            .iter()
+            .enumerate()
+            .position(|(index, interaction)| {
+                let Interaction::WebSocket { url: recorded, .. } = interaction else {
+                    return false;
+                };
+                self.same_url(recorded, url) && (self.rules.allow_repeats || !tape.played[index])
+            });
+        let Some(index) = found else {
+            let message = format!("Cassette has no recorded WebSocket for {}", url);
+            return Err(Failure::new(ErrorCode::Cassette, Phase::Setup, message).into());
+        };
+        tape.played[index] = true;
+        match &tape.interactions[index] {
+            Interaction::WebSocket { frames, .. } => Ok(frames.iter().cloned().collect()),
+            Interaction::Http { .. } => unreachable!(),
+        }
+    }
+
+    /// Start recording a WebSocket to `url`; frames are added with the index returned
+    pub fn record_websocket(&self, url: &str) -> usize {
+        self.push(Interaction::WebSocket {
+            url: url.to_string(),
+            frames: Vec::new(),
+        })
+    }
+
+    pub fn record_frame(&self, index: usize, frame: Frame) {
+        let mut tape = self.tape.lock().unwrap();
+        if let Some(Interaction::WebSocket { frames, .. }) = tape.interactions.get_mut(index) {
+            frames.push(frame);
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        let file = CassetteFile {
+            version: FORMAT_VERSION,
+            interactions: self.tape.lock().unwrap().interactions.clone(),
+        };
+        serde_json::to_string_pretty(&file).context("Failed to serialize cassette")
+    }
+
+    fn push(&self, interaction: Interaction) -> usize {
+        let mut tape = self.tape.lock().unwrap();
+        tape.interactions.push(interaction);
+        tape.played.push(false);
+        tape.interactions.len() - 1
+    }
+
+    fn matches(&self, recorded: &RecordedRequest, request: &RecordedRequest) -> bool {
+        recorded.method == request.method
+            && self.same_url(&recorded.url, &request.url)
+            && (!self.rules.body || recorded.body == request.body)
+    }
+
+    fn same_url(&self, recorded: &str, url: &str) -> bool {
+        let ignored = &self.rules.ignore_query_params;
+        strip_query_params(recorded, ignored) == strip_query_params(url, ignored)
+    }
+}
+
+pub fn global_cassette() -> Option<Arc<Cassette>> {
+    GLOBAL_CASSETTE.read().unwrap().clone()
+}
+
+pub fn set_global_cassette(cassette: Option<Arc<Cassette>>) {
+    *GLOBAL_CASSETTE.write().unwrap() = cassette;
+}
+
+/// What a replayed WebSocket expected instead of the message the client sent
+pub fn unexpected_send(expected: Option<&Frame>, sent: &Payload) -> anyhow::Error {
+    let expected = match expected {
+        Some(Frame::Send { data }) => format!("expected {}", data.describe()),
+        Some(Frame::Receive { .. }) | Some(Frame::Close) => {
+            "expected the client to wait for the server".to_string()
+        }
+        None => "no more messages were recorded".to_string(),
+    };
+    anyhow::anyhow!(
+        "Cassette WebSocket got {}, but {}",
+        sent.describe(),
+        expected
+    )
+}
+
+// The URL without its fragment and the named query parameters; the rest keep their order
+fn strip_query_params(url: &str, ignored: &[String]) -> String {
+    let url = url.split_once('#').map_or(url, |(url, _)| url);
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+    let kept: Vec<&str> = query
+        .split('&')
+        .filter(|pair| {
+            let name = pair.split_once('=').map_or(*pair, |(name, _)| name);
+            !pair.is_empty() && !ignored.iter().any(|ignored| ignored == name)
+        })
+        .collect();
+    if kept.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}?{}", base, kept.join("&"))
+    }
+}
@@ -1,14 +1,39 @@
+use crate::cassette::{global_cassette, Cassette, CassetteMode, RecordedRequest};
+use crate::connection_pool::{ConnectionObserver, ObservedOrigin, PoolSettings};
+use crate::cookies::SessionCookies;
+use crate::dns::SystemResolver;
+use crate::emulation::{parse_os, resolve_profile, EmulationProfile, EmulationSettings};
+use crate::errors::{ErrorCode, Failure, Phase, RequestTarget};
+use crate::har::{global_recorder, Exchange, Recorder};
+use crate::hooks::{AfterResponseFn, BeforeRequestFn, HookRequest, Hooks};
+use crate::http_cache::{
+    unix_seconds, CacheMode, CacheSettings, CacheStatus, CachedResponse, HttpCache,
+};
+use crate::proxy::ProxySettings;
+use crate::proxy_pool::ProxyPool;
+use crate::proxy_resolver::ProxyResolver;
+use crate::rate_limit::{host_limiter, Limiter, RateLimits, Slot};
+use crate::snapshot::{SessionSnapshot, SnapshotDefaults, SNAPSHOT_VERSION};
 use anyhow::{Context, Result};
 use indexmap::IndexMap;
+use moka::notification::RemovalCause;
 use moka::sync::Cache;
+use moka::Expiry;
 use once_cell::sync::Lazy;
-use serde_json::Value;
-use std::sync::Arc;
-use std::time::Duration;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Once, RwLock, RwLockReadGuard, Weak};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::runtime::Runtime;
+use tokio::sync::oneshot;
 use uuid::Uuid;
-use wreq::{Client as HttpClient, Proxy};
-use wreq_util::Emulation;
+use wreq::cookie::CookieStore;
+use wreq::header::{HeaderMap, HeaderName, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH};
+use wreq::redirect::History;
+use wreq::{Client as HttpClient, Extension, Proxy, StatusCode, Uri};
 
 pub static HTTP_RUNTIME: Lazy<Runtime> = Lazy::new(|| {
     tokio::runtime::Builder::new_multi_thread()
@@ -19,18 +44,43 @@ pub static HTTP_RUNTIME: Lazy<Runtime> = Lazy::new(|| {
 
 static SESSION_MANAGER: Lazy<SessionManager> = Lazy::new(SessionManager::new);
 
+static EVICTION_SWEEPER: Once = Once::new();
+
+// How long an evicted session id keeps failing instead of being silently recreated
+const EVICTED_MEMORY: Duration = Duration::from_secs(3600);
+const EVICTED_CAPACITY: u64 = 10_000;
+
+// Clients shared by requests without a session, one per profile and proxy
+static STATELESS_CLIENTS: Lazy<Cache<StatelessKey, Arc<SessionEntry>>> = Lazy::new(|| {
+    Cache::builder()
+        .max_capacity(STATELESS_CAPACITY)
+        .time_to_idle(STATELESS_IDLE)
+        .build()
+});
+
+const STATELESS_CAPACITY: u64 = 64;
+const STATELESS_IDLE: Duration = Duration::from_secs(300);
+
+// Requests JS can still abort, by the id it gave them
+static CANCELLABLE: Lazy<Mutex<HashMap<String, oneshot::Sender<()>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
 #[derive(Debug, Clone)]
 pub struct RequestOptions {
     pub url: String,
-    pub emulation: Emulation,
+    pub emulation: EmulationProfile,
     pub headers: IndexMap<String, String>,
     pub method: String,
     pub body: Option<String>,
-    pub proxy: Option<String>,
+    pub proxy: Option<ProxySettings>,
+    pub proxy_pool: Option<Arc<ProxyPool>>,
     pub timeout: u64,
-    pub session_id: String,
-    pub ephemeral: bool,
+    /// `None` for a stateless request: it shares a cookieless client with other requests
+    /// using the same profile and proxy
+    pub session_id: Option<String>,
     pub disable_default_headers: bool,
+    /// Only used by sessions with a cache
+    pub cache_mode: CacheMode,
 }
 
 #[derive(Debug, Clone)]
@@ -40,119 +90,932 @@ pub struct Response {
     pub body: String,
     pub cookies: IndexMap<String, String>,
     pub url: String,
+    /// Proxy the request was sent through
+    pub proxy: Option<String>,
+    /// `None` unless the session has a cache and the request could use it
+    pub cache_status: Option<CacheStatus>,
 }
 
 #[derive(Clone)]
-struct SessionConfig {
-    emulation: Emulation,
+pub struct SessionConfig {
+    emulation: EmulationProfile,
     label: String,
-    proxy: Option<String>,
+    proxy: Option<ProxySettings>,
+    // Picks a proxy per request instead of a fixed `proxy`
+    proxy_pool: Option<Arc<ProxyPool>>,
+    // Asks JS for the proxy per request; only sessions have one
+    proxy_resolver: Option<Arc<ProxyResolver>>,
+    connection_pool: PoolSettings,
+    rate_limits: RateLimits,
+    cache: Option<CacheSettings>,
 }
 
 impl SessionConfig {
     fn from_request(options: &RequestOptions) -> Self {
         Self {
             emulation: options.emulation.clone(),
-            label: emulation_label(&options.emulation),
+            label: options.emulation.label(),
             proxy: options.proxy.clone(),
+            proxy_pool: options.proxy_pool.clone(),
+            proxy_resolver: None,
+            connection_pool: PoolSettings::default(),
+            rate_limits: RateLimits::default(),
+            cache: None,
         }
     }
 
-    fn new(emulation: Emulation, proxy: Option<String>) -> Self {
-        let label = emulation_label(&emulation);
+    pub fn new(
+        emulation: EmulationProfile,
+        proxy: Option<ProxySettings>,
+        proxy_pool: Option<Arc<ProxyPool>>,
+        proxy_resolver: Option<Arc<ProxyResolver>>,
+        connection_pool: PoolSettings,
+        rate_limits: RateLimits,
+        cache: Option<CacheSettings>,
+    ) -> Self {
+        let label = emulation.label();
         Self {
             emulation,
             label,
             proxy,
+            proxy_pool,
+            proxy_resolver,
+            connection_pool,
+            rate_limits,
+            cache,
         }
     }
 
+    // Requests never carry the resolver, the pool limits, the rate limits or the cache, so
+    // they are left out of the comparison
     fn matches(&self, other: &SessionConfig) -> bool {
-        self.label == other.label && self.proxy == other.proxy
+        let same_pool = match (&self.proxy_pool, &other.proxy_pool) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        self.label == other.label && self.proxy == other.proxy && same_pool
+    }
+}
+
+// Identifies a shared stateless client. Only part of the config is hashed; `matches`
+// tells apart the rest.
+#[derive(Clone)]
+struct StatelessKey(SessionConfig);
+
+impl PartialEq for StatelessKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.matches(&other.0)
+    }
+}
+
+impl Eq for StatelessKey {}
+
+impl Hash for StatelessKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.label.hash(state);
+        self.0.proxy.as_ref().map(|proxy| &proxy.url).hash(state);
+        self.0.proxy_pool.as_ref().map(Arc::as_ptr).hash(state);
+    }
+}
+
+/// Changes applied to a live session by `reconfigure_session`
+#[derive(Debug, Clone, Default)]
+pub struct SessionUpdate {
+    pub emulation: Option<EmulationProfile>,
+    /// `Some(None)` removes the proxy, `None` keeps the current one
+    pub proxy: Option<Option<ProxySettings>>,
+    /// Same as `proxy`; setting any of `proxy`, `proxy_pool` and `proxy_resolver`
+    /// replaces the others
+    pub proxy_pool: Option<Option<Arc<ProxyPool>>>,
+    pub proxy_resolver: Option<Option<Arc<ProxyResolver>>>,
+    pub connection_pool: Option<PoolSettings>,
+    pub rate_limits: Option<RateLimits>,
+    /// `Some(None)` removes the cache, `None` keeps the current one
+    pub cache: Option<Option<CacheSettings>>,
+    /// Same as `cache`
+    pub recorder: Option<Option<Arc<Recorder>>>,
+    pub cassette: Option<Option<Arc<Cassette>>>,
+    pub before_request: Option<Option<Arc<BeforeRequestFn>>>,
+    pub after_response: Option<Option<Arc<AfterResponseFn>>>,
+}
+
+/// What a new session is given from JS besides its client settings. None of it is saved in
+/// snapshots.
+#[derive(Clone, Default)]
+pub struct SessionExtras {
+    pub recorder: Option<Arc<Recorder>>,
+    pub cassette: Option<Arc<Cassette>>,
+    pub hooks: Hooks,
+}
+
+/// What `reset_session` throws away
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResetMode {
+    /// Empty the cookie jar; pooled connections stay open
+    Cookies,
+    /// Close pooled connections, so the next requests make new TLS handshakes; cookies stay
+    Connections,
+    /// Both, leaving the session as if it was just created
+    All,
+}
+
+impl ResetMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+    }
+}
+
+/// Expiry and capacity limits for managed sessions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionLimits {
+    /// Maximum lifetime of a session, measured from creation
+    pub time_to_live: Option<Duration>,
+    /// Maximum time a session may go unused
+    pub time_to_idle: Option<Duration>,
+    /// Maximum number of sessions; never-expiring sessions are not counted
+    pub max_sessions: Option<u64>,
+}
+
+impl Default for SessionLimits {
+    fn default() -> Self {
+        Self {
+            time_to_live: None,
+            time_to_idle: Some(Duration::from_secs(300)),
+            max_sessions: None,
+        }
+    }
+}
+
+/// Why a session was evicted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionCause {
+    Expired,
+    Capacity,
+}
+
+impl EvictionCause {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EvictionCause::Expired => "expired",
+            EvictionCause::Capacity => "capacity",
+        }
     }
 }
 
+/// A session removed by the cache rather than by the caller
+#[derive(Debug, Clone)]
+pub struct SessionEviction {
+    pub session_id: String,
+    pub cause: EvictionCause,
+    pub browser: String,
+    pub proxy: Option<String>,
+}
+
+pub type EvictionHandler = Arc<dyn Fn(SessionEviction) + Send + Sync>;
+
+/// Snapshot of a live session for introspection
+#[derive(Debug, Clone)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub browser: String,
+    pub os: String,
+    pub skip_http2: bool,
+    pub skip_headers: bool,
+    pub proxy: Option<String>,
+    pub never_expire: bool,
+    /// Milliseconds since the Unix epoch
+    pub created_at: u64,
+    /// Milliseconds since the Unix epoch of the last request, if any
+    pub last_used_at: Option<u64>,
+    pub request_count: u64,
+    pub cookie_count: usize,
+}
+
 #[derive(Clone)]
 struct SessionEntry {
     client: Arc<HttpClient>,
+    // Connections of this client; a rebuilt client starts with none
+    connections: Arc<ConnectionObserver>,
+    // Profile headers with the session's default headers merged in, as the client sends them
+    default_headers: HeaderMap,
     config: SessionConfig,
+    state: SessionState,
+}
+
+// Session state that survives client rebuilds
+#[derive(Clone)]
+struct SessionState {
+    // Owned outside the client so it can be handed to a rebuilt client
+    jar: Arc<SessionCookies>,
+    // Sent with every request unless the request sets the same header
+    headers: IndexMap<String, String>,
+    created_at: Instant,
+    never_expire: bool,
+    stats: Arc<SessionStats>,
+    // Kept across rebuilds so requests in flight and queued ones count against the same limits
+    limiter: Arc<Limiter>,
+    // Kept across rebuilds, so a reconfigured session still has what it stored
+    cache: Option<Arc<HttpCache>>,
+    recorder: Option<Arc<Recorder>>,
+    cassette: Option<Arc<Cassette>>,
+    hooks: Hooks,
+}
+
+impl SessionState {
+    fn new(never_expire: bool) -> Self {
+        Self {
+            jar: Arc::new(SessionCookies::default()),
+            headers: IndexMap::new(),
+            created_at: Instant::now(),
+            never_expire,
+            stats: Arc::new(SessionStats::new()),
+            limiter: Arc::new(Limiter::default()),
+            cache: None,
+            recorder: None,
+            cassette: None,
+            hooks: Hooks::default(),
+        }
+    }
+}
+
+// Usage counters shared by every client a session goes through
+struct SessionStats {
+    created_at: u64,
+    // 0 until the first request
+    last_used_at: AtomicU64,
+    requests: AtomicU64,
+}
+
+impl SessionStats {
+    fn new() -> Self {
+        Self {
+            created_at: unix_millis(),
+            last_used_at: AtomicU64::new(0),
+            requests: AtomicU64::new(0),
+        }
+    }
+
+    fn record_request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.last_used_at.store(unix_millis(), Ordering::Relaxed);
+    }
+}
+
+impl SessionEntry {
+    fn info(&self, session_id: &str) -> SessionInfo {
+        let stats = &self.state.stats;
+        let settings = self.config.emulation.settings();
+        let last_used_at = stats.last_used_at.load(Ordering::Relaxed);
+
+        SessionInfo {
+            session_id: session_id.to_string(),
+            browser: self.config.emulation.name(),
+            os: settings.os_name(),
+            skip_http2: settings.skip_http2,
+            skip_headers: settings.skip_headers,
+            proxy: self.config.proxy.as_ref().and_then(ProxySettings::label),
+            never_expire: self.state.never_expire,
+            created_at: stats.created_at,
+            last_used_at: (last_used_at > 0).then_some(last_used_at),
+            request_count: stats.requests.load(Ordering::Relaxed),
+            cookie_count: self.state.jar.count(),
+        }
+    }
+}
+
+// Per-entry expiry so never-expiring sessions can opt out of the TTL/idle limits
+struct SessionExpiry {
+    limits: SessionLimits,
+}
+
+impl SessionExpiry {
+    fn remaining(&self, entry: &SessionEntry, now: Instant) -> Option<Duration> {
+        if entry.state.never_expire {
+            return None;
+        }
+
+        let live = self
+            .limits
+            .time_to_live
+            .map(|ttl| ttl.saturating_sub(now.saturating_duration_since(entry.state.created_at)));
+
+        match (live, self.limits.time_to_idle) {
+            (Some(live), Some(idle)) => Some(live.min(idle)),
+            (live, idle) => live.or(idle),
+        }
+    }
+}
+
+impl Expiry<String, Arc<SessionEntry>> for SessionExpiry {
+    fn expire_after_create(
+        &self,
+        _session_id: &String,
+        entry: &Arc<SessionEntry>,
+        created_at: Instant,
+    ) -> Option<Duration> {
+        self.remaining(entry, created_at)
+    }
+
+    fn expire_after_read(
+        &self,
+        _session_id: &String,
+        entry: &Arc<SessionEntry>,
+        read_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+        _last_modified_at: Instant,
+    ) -> Option<Duration> {
+        self.remaining(entry, read_at)
+    }
+
+    fn expire_after_update(
+        &self,
+        _session_id: &String,
+        entry: &Arc<SessionEntry>,
+        updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        self.remaining(entry, updated_at)
+    }
 }
 
 struct SessionManager {
-    cache: Cache<String, Arc<SessionEntry>>,
+    cache: RwLock<Cache<String, Arc<SessionEntry>>>,
+    // The cached entries by id, so they can be looked at without counting as a use
+    index: SessionIndex,
+    inserting: Mutex<()>,
+    limits: RwLock<SessionLimits>,
+    // Recently evicted ids, so using one fails loudly instead of starting an empty jar
+    evicted: Cache<String, EvictionCause>,
+    eviction_handler: Arc<RwLock<Option<EvictionHandler>>>,
 }
 
 impl SessionManager {
     fn new() -> Self {
+        let limits = SessionLimits::default();
+        let evicted = Cache::builder()
+            .max_capacity(EVICTED_CAPACITY)
+            .time_to_live(EVICTED_MEMORY)
+            .build();
+        let eviction_handler = Arc::new(RwLock::new(None));
+        let index = SessionIndex::default();
+        let cache = build_cache(
+            limits,
+            evicted.clone(),
+            eviction_handler.clone(),
+            index.clone(),
+        );
+
         Self {
-            cache: Cache::builder()
-                .time_to_idle(Duration::from_secs(300))
-                .build(),
+            cache: RwLock::new(cache),
+            index,
+            inserting: Mutex::new(()),
+            limits: RwLock::new(limits),
+            evicted,
+            eviction_handler,
+        }
+    }
+
+    // Callers keep the guard while they use the cache, so `configure` cannot swap it
+    // between their lookup and their insert
+    fn cache(&self) -> RwLockReadGuard<'_, Cache<String, Arc<SessionEntry>>> {
+        self.cache.read().unwrap()
+    }
+
+    fn insert(
+        &self,
+        cache: &Cache<String, Arc<SessionEntry>>,
+        session_id: String,
+        entry: Arc<SessionEntry>,
+    ) {
+        // One at a time, so the index ends up on whichever entry the cache kept
+        let _inserting = self.inserting.lock().unwrap();
+        cache.insert(session_id.clone(), entry.clone());
+        self.index.set(&session_id, &entry);
+    }
+
+    fn limits(&self) -> SessionLimits {
+        *self.limits.read().unwrap()
+    }
+
+    // Rebuild the cache with new limits, carrying every live session over
+    fn configure(&self, limits: SessionLimits) {
+        let mut cache = self.cache.write().unwrap();
+        let next = build_cache(
+            limits,
+            self.evicted.clone(),
+            self.eviction_handler.clone(),
+            self.index.clone(),
+        );
+
+        for (session_id, entry) in cache.iter() {
+            next.insert(session_id.as_ref().clone(), entry);
         }
+
+        *cache = next;
+        *self.limits.write().unwrap() = limits;
     }
 
-    fn client_for(&self, session_id: &str, config: SessionConfig) -> Result<Arc<HttpClient>> {
-        if let Some(entry) = self.cache.get(session_id) {
+    fn set_eviction_handler(&self, handler: Option<EvictionHandler>) {
+        let enabled = handler.is_some();
+        *self.eviction_handler.write().unwrap() = handler;
+
+        if enabled {
+            start_eviction_sweeper();
+        }
+    }
+
+    fn entry_for(&self, session_id: &str, config: SessionConfig) -> Result<Arc<SessionEntry>> {
+        let cache = self.cache();
+
+        if let Some(entry) = cache.get(session_id) {
             if entry.config.matches(&config) {
-                return Ok(entry.client.clone());
+                entry.state.stats.record_request();
+                return Ok(entry);
             } else {
-                anyhow::bail!(
-                    "Session '{}' was created with different browser/proxy configuration",
+                return Err(session_failure(format!(
+                    "Session '{}' was created with different browser/proxy configuration; reconfigure the session to change it",
                     session_id
-                );
+                )));
             }
         }
 
-        let entry = self.build_entry(config)?;
-        self.cache.insert(session_id.to_string(), entry.clone());
-        Ok(entry.client.clone())
+        // Flush pending expirations so an expired session is reported as evicted below
+        cache.run_pending_tasks();
+        if let Some(cause) = self.evicted.get(session_id) {
+            return Err(session_failure(format!(
+                "Session '{}' was evicted ({}) and its cookies were lost; create it again to continue",
+                session_id,
+                cause.as_str()
+            )));
+        }
+
+        let entry = self.build_entry(config, SessionState::new(false))?;
+        entry.state.stats.record_request();
+        self.insert(&cache, session_id.to_string(), entry.clone());
+        Ok(entry)
     }
 
-    fn build_entry(&self, config: SessionConfig) -> Result<Arc<SessionEntry>> {
-        let client = Arc::new(build_client(&config)?);
-        Ok(Arc::new(SessionEntry { client, config }))
+    // Session clients always keep cookies
+    fn build_entry(&self, config: SessionConfig, state: SessionState) -> Result<Arc<SessionEntry>> {
+        build_entry(config, state, true)
     }
 
-    fn create_session(&self, session_id: String, config: SessionConfig) -> Result<String> {
-        let entry = self.build_entry(config)?;
-        self.cache.insert(session_id.clone(), entry);
+    fn existing(&self, session_id: &str) -> Result<Arc<SessionEntry>> {
+        self.cache()
+            .get(session_id)
+            .ok_or_else(|| session_not_found(session_id))
+    }
+
+    fn create_session(
+        &self,
+        session_id: String,
+        config: SessionConfig,
+        never_expire: bool,
+        headers: IndexMap<String, String>,
+        extras: SessionExtras,
+    ) -> Result<String> {
+        let state = SessionState {
+            headers,
+            recorder: extras.recorder,
+            cassette: extras.cassette,
+            hooks: extras.hooks,
+            limiter: Arc::new(Limiter::new(config.rate_limits)),
+            cache: open_cache(&config)?,
+            ..SessionState::new(never_expire)
+        };
+        let entry = self.build_entry(config, state)?;
+        self.evicted.invalidate(&session_id);
+        self.insert(&self.cache(), session_id.clone(), entry);
         Ok(session_id)
     }
 
-    fn clear_session(&self, session_id: &str) -> Result<()> {
-        let existing = self
-            .cache
-            .get(session_id)
-            .ok_or_else(|| anyhow::anyhow!("Session '{}' not found", session_id))?;
-        let config = existing.config.clone();
-        let entry = self.build_entry(config)?;
-        self.cache.insert(session_id.to_string(), entry);
+    fn reset_session(&self, session_id: &str, mode: ResetMode) -> Result<()> {
+        let existing = self.existing(session_id)?;
+        if mode == ResetMode::Cookies {
+            // The client and its connections stay; requests in flight may still add cookies
+            existing.state.jar.clear();
+            return Ok(());
+        }
+
+        // A new client starts with an empty pool. A full reset also gets a new jar, so
+        // requests still in flight on the old client cannot put cookies back in.
+        let jar = match mode {
+            ResetMode::Connections => existing.state.jar.clone(),
+            _ => Arc::new(SessionCookies::default()),
+        };
+        let state = SessionState {
+            jar,
+            ..existing.state.clone()
+        };
+        let entry = self.build_entry(existing.config.clone(), state)?;
+        self.insert(&self.cache(), session_id.to_string(), entry);
+        Ok(())
+    }
+
+    fn reconfigure_session(&self, session_id: &str, update: SessionUpdate) -> Result<()> {
+        let existing = self.existing(session_id)?;
+
+        let mut config = existing.config.clone();
+        if let Some(emulation) = update.emulation {
+            config = SessionConfig::new(
+                emulation,
+                config.proxy,
+                config.proxy_pool,
+                config.proxy_resolver,
+                config.connection_pool,
+                config.rate_limits,
+                config.cache,
+            );
+        }
+        if let Some(connection_pool) = update.connection_pool {
+            config.connection_pool = connection_pool;
+        }
+        let mut state = existing.state.clone();
+        if let Some(rate_limits) = update.rate_limits.filter(|l| *l != config.rate_limits) {
+            // Requests already admitted or waiting stay with the old limiter
+            config.rate_limits = rate_limits;
+            state.limiter = Arc::new(Limiter::new(rate_limits));
+        }
+        if let Some(cache) = update.cache.filter(|cache| *cache != config.cache) {
+            // A new cache starts empty; a disk store finds what its directory already holds
+            config.cache = cache;
+            state.cache = open_cache(&config)?;
+        }
+        if let Some(recorder) = update.recorder {
+            state.recorder = recorder;
+        }
+        if let Some(cassette) = update.cassette {
+            state.cassette = cassette;
+        }
+        if let Some(before_request) = update.before_request {
+            state.hooks.before_request = before_request;
+        }
+        if let Some(after_response) = update.after_response {
+            state.hooks.after_response = after_response;
+        }
+        // Setting one proxy source replaces the others; removing one leaves them alone, so
+        // `{ proxy, proxyPool: null }` ends up with the proxy
+        if let Some(proxy) = update.proxy {
+            if proxy.is_some() {
+                config.proxy_pool = None;
+                config.proxy_resolver = None;
+            }
+            config.proxy = proxy;
+        }
+        if let Some(proxy_pool) = update.proxy_pool {
+            if proxy_pool.is_some() {
+                config.proxy = None;
+                config.proxy_resolver = None;
+            }
+            config.proxy_pool = proxy_pool;
+        }
+        if let Some(proxy_resolver) = update.proxy_resolver {
+            if proxy_resolver.is_some() {
+                config.proxy = None;
+                config.proxy_pool = None;
+            }
+            config.proxy_resolver = proxy_resolver;
+        }
+
+        // Requests still in flight keep the old client, but both share the jar,
+        // so cookies they receive are not lost
+        let entry = self.build_entry(config, state)?;
+        self.insert(&self.cache(), session_id.to_string(), entry);
         Ok(())
     }
 
     fn drop_session(&self, session_id: &str) {
-        self.cache.invalidate(session_id);
+        self.cache().invalidate(session_id);
+    }
+
+    // Unlike `get`, `contains_key` doesn't count as using the session
+    fn peek(&self, session_id: &str) -> Option<Arc<SessionEntry>> {
+        if !self.cache().contains_key(session_id) {
+            return None;
+        }
+        self.index.get(session_id)
+    }
+
+    fn session_info(&self, session_id: &str) -> Option<SessionInfo> {
+        self.peek(session_id).map(|entry| entry.info(session_id))
+    }
+
+    fn observed_connections(&self, session_id: &str) -> Result<Vec<ObservedOrigin>> {
+        let entry = self
+            .peek(session_id)
+            .ok_or_else(|| session_not_found(session_id))?;
+        Ok(entry.connections.observed())
+    }
+
+    fn snapshot_session(
+        &self,
+        session_id: &str,
+        mut defaults: SnapshotDefaults,
+    ) -> Result<SessionSnapshot> {
+        let entry = self
+            .peek(session_id)
+            .ok_or_else(|| session_not_found(session_id))?;
+        let settings = entry.config.emulation.settings();
+        defaults.headers = entry.state.headers.clone();
+        let proxy = entry.config.proxy.clone();
+
+        Ok(SessionSnapshot {
+            version: SNAPSHOT_VERSION,
+            session_id: session_id.to_string(),
+            browser: entry.config.emulation.name(),
+            os: settings.os_name(),
+            skip_http2: settings.skip_http2,
+            skip_headers: settings.skip_headers,
+            proxy_mode: proxy.as_ref().map(|proxy| proxy.mode),
+            proxy: proxy.as_ref().and_then(|proxy| proxy.url.clone()),
+            proxy_http: proxy.as_ref().and_then(|proxy| proxy.http.clone()),
+            proxy_https: proxy.as_ref().and_then(|proxy| proxy.https.clone()),
+            proxy_ws: proxy.as_ref().and_then(|proxy| proxy.ws.clone()),
+            no_proxy: proxy
+                .as_ref()
+                .map(|proxy| proxy.no_proxy.clone())
+                .unwrap_or_default(),
+            proxy_username: proxy.as_ref().and_then(|proxy| proxy.username.clone()),
+            proxy_password: proxy.as_ref().and_then(|proxy| proxy.password.clone()),
+            proxy_headers: proxy
+                .as_ref()
+                .map(|proxy| proxy.headers.clone())
+                .unwrap_or_default(),
+            proxy_pool: entry
+                .config
+                .proxy_pool
+                .as_ref()
+                .map(|pool| pool.config().clone()),
+            connection_pool: Some(entry.config.connection_pool)
+                .filter(|pool| *pool != PoolSettings::default()),
+            rate_limits: Some(entry.config.rate_limits).filter(|limits| !limits.is_unlimited()),
+            cache: entry.config.cache.clone(),
+            never_expire: entry.state.never_expire,
+            created_at: entry.state.stats.created_at,
+            defaults,
+            cookies: entry.state.jar.export(),
+        })
+    }
+
+    fn restore_session(
+        &self,
+        session_id: String,
+        config: SessionConfig,
+        snapshot: &SessionSnapshot,
+    ) -> Result<String> {
+        let stats = SessionStats {
+            created_at: snapshot.created_at,
+            ..SessionStats::new()
+        };
+        let state = SessionState {
+            jar: Arc::new(SessionCookies::import(&snapshot.cookies)),
+            headers: snapshot.defaults.headers.clone(),
+            stats: Arc::new(stats),
+            limiter: Arc::new(Limiter::new(config.rate_limits)),
+            cache: open_cache(&config)?,
+            ..SessionState::new(snapshot.never_expire)
+        };
+
+        let entry = self.build_entry(config, state)?;
+        self.evicted.invalidate(&session_id);
+        self.insert(&self.cache(), session_id.clone(), entry);
+        Ok(session_id)
+    }
+
+    fn list_sessions(&self) -> Vec<SessionInfo> {
+        let mut sessions: Vec<SessionInfo> = self
+            .cache()
+            .iter()
+            .map(|(id, entry)| entry.info(&id))
+            .collect();
+        sessions.sort_by_key(|info| info.created_at);
+        sessions
+    }
+}
+
+fn build_cache(
+    limits: SessionLimits,
+    evicted: Cache<String, EvictionCause>,
+    eviction_handler: Arc<RwLock<Option<EvictionHandler>>>,
+    index: SessionIndex,
+) -> Cache<String, Arc<SessionEntry>> {
+    let mut builder = Cache::builder()
+        .expire_after(SessionExpiry { limits })
+        .eviction_listener(
+            move |session_id: Arc<String>, entry: Arc<SessionEntry>, cause: RemovalCause| {
+                if cause != RemovalCause::Replaced {
+                    index.remove(&session_id, &entry);
+                }
+
+                // Explicit drops and replacements are the caller's doing, not evictions
+                let cause = match cause {
+                    RemovalCause::Expired => EvictionCause::Expired,
+                    RemovalCause::Size => EvictionCause::Capacity,
+                    RemovalCause::Explicit | RemovalCause::Replaced => return,
+                };
+
+                evicted.insert(session_id.as_ref().clone(), cause);
+                tracing::info!(%session_id, reason = cause.as_str(), "session evicted");
+
+                let handler = eviction_handler.read().unwrap().clone();
+                if let Some(handler) = handler {
+                    handler(SessionEviction {
+                        session_id: session_id.as_ref().clone(),
+                        cause,
+                        browser: entry.config.emulation.name(),
+                        proxy: entry.config.proxy.as_ref().and_then(ProxySettings::label),
+                    });
+                }
+            },
+        );
+
+    if let Some(max_sessions) = limits.max_sessions {
+        builder = builder
+            .max_capacity(max_sessions)
+            .weigher(|_, entry: &Arc<SessionEntry>| u32::from(!entry.state.never_expire));
     }
+
+    builder.build()
 }
 
-pub async fn make_request(options: RequestOptions) -> Result<Response> {
-    let session_id = options.session_id.clone();
-    let ephemeral = options.ephemeral;
+/// Entries of the session cache by id. Kept by the manager's inserts and the cache's
+/// removals; the entries themselves stay owned by the cache.
+#[derive(Clone, Default)]
+struct SessionIndex(Arc<RwLock<HashMap<String, Weak<SessionEntry>>>>);
 
-    let result = make_request_inner(options).await;
+impl SessionIndex {
+    fn get(&self, session_id: &str) -> Option<Arc<SessionEntry>> {
+        self.0
+            .read()
+            .unwrap()
+            .get(session_id)
+            .and_then(Weak::upgrade)
+    }
 
-    if ephemeral {
-        SESSION_MANAGER.drop_session(&session_id);
+    fn set(&self, session_id: &str, entry: &Arc<SessionEntry>) {
+        self.0
+            .write()
+            .unwrap()
+            .insert(session_id.to_string(), Arc::downgrade(entry));
     }
 
-    result
+    // Only if the id still points at the removed entry; it may have been replaced since
+    fn remove(&self, session_id: &str, entry: &Arc<SessionEntry>) {
+        let mut index = self.0.write().unwrap();
+        if index
+            .get(session_id)
+            .is_some_and(|indexed| std::ptr::eq(indexed.as_ptr(), Arc::as_ptr(entry)))
+        {
+            index.remove(session_id);
+        }
+    }
+}
+
+// moka only expires entries while the cache is in use; sweep periodically so
+// eviction events are delivered even when the process is otherwise idle
+fn start_eviction_sweeper() {
+    EVICTION_SWEEPER.call_once(|| {
+        std::thread::Builder::new()
+            .name("wreq-session-sweeper".to_string())
+            .spawn(|| loop {
+                std::thread::sleep(Duration::from_secs(1));
+                SESSION_MANAGER.cache().run_pending_tasks();
+            })
+            .expect("Failed to spawn session sweeper thread");
+    });
+}
+
+fn build_entry(
+    config: SessionConfig,
+    state: SessionState,
+    cookies: bool,
+) -> Result<Arc<SessionEntry>> {
+    let (client, default_headers) = build_client(&config, &state, cookies)?;
+    Ok(Arc::new(SessionEntry {
+        client: Arc::new(client),
+        connections: Arc::new(ConnectionObserver::new(config.connection_pool)),
+        default_headers,
+        config,
+        state,
+    }))
+}
+
+// The shared client for stateless requests with `config`, built on first use. It keeps no
+// cookies, so its connections can be reused without one caller seeing another's cookies.
+fn stateless_entry(config: SessionConfig) -> Result<Arc<SessionEntry>> {
+    let key = StatelessKey(config);
+    if let Some(entry) = STATELESS_CLIENTS.get(&key) {
+        // A custom profile registered again under the same name needs a new client
+        if entry.config.emulation.same_definition(&key.0.emulation) {
+            return Ok(entry);
+        }
+    }
+
+    let entry = build_entry(key.0.clone(), SessionState::new(false), false)?;
+    STATELESS_CLIENTS.insert(key, entry.clone());
+    Ok(entry)
+}
+
+fn session_failure(message: String) -> anyhow::Error {
+    Failure::new(ErrorCode::Session, Phase::Setup, message).into()
+}
+
+pub fn session_not_found(session_id: &str) -> anyhow::Error {
+    session_failure(format!("Session '{}' not found", session_id))
 }
 
-async fn make_request_inner(options: RequestOptions) -> Result<Response> {
-    let client = {
+pub async fn make_request(mut options: RequestOptions) -> Result<Response> {
+    let entry = {
         let config = SessionConfig::from_request(&options);
-        SESSION_MANAGER.client_for(&options.session_id, config)?
+        match &options.session_id {
+            Some(session_id) => SESSION_MANAGER.entry_for(session_id, config)?,
+            None => stateless_entry(config)?,
+        }
+    };
+
+    // Hooks are awaited within the request's timeout, and see it as the caller made it: the
+    // session's default headers are merged in later
+    let hooks = &entry.state.hooks;
+    let timeout = Duration::from_millis(options.timeout);
+    if let Some(response) = hooks.before_request(&mut options, timeout).await? {
+        return Ok(response);
+    }
+    let request = hooks
+        .after_response
+        .as_ref()
+        .map(|_| HookRequest::new(&options));
+
+    let response = send_request(&entry, options).await?;
+    match request {
+        Some(request) => hooks.after_response(request, response, timeout).await,
+        None => Ok(response),
+    }
+}
+
+async fn send_request(entry: &SessionEntry, options: RequestOptions) -> Result<Response> {
+    // A cassette that replays answers without the network; one that records keeps the outcome
+    let cassette = entry.state.cassette.clone().or_else(global_cassette);
+    let taped = cassette.map(|cassette| {
+        let request = RecordedRequest::new(&options.method, &options.url, options.body.as_deref());
+        (cassette, request)
+    });
+    let replaying = taped
+        .as_ref()
+        .filter(|(cassette, _)| cassette.mode() == CassetteMode::Replay);
+    if let Some((cassette, request)) = replaying {
+        let response = cassette.replay(request)?;
+        if options.session_id.is_some() {
+            replay_cookies(entry, &response);
+        }
+        return Ok(response);
+    }
+
+    // The session's recorder and the global one, once if they are the same
+    let mut recorders: Vec<Arc<Recorder>> = entry.state.recorder.iter().cloned().collect();
+    let global = global_recorder().filter(|global| {
+        !recorders
+            .iter()
+            .any(|recorder| Arc::ptr_eq(recorder, global))
+    });
+    recorders.extend(global);
+    let result = if recorders.is_empty() {
+        make_request_inner(entry, options, None).await
+    } else {
+        let mut exchange = Exchange::new(&options.method, &options.url);
+        let result = make_request_inner(entry, options, Some(&mut exchange)).await;
+        for recorder in recorders {
+            recorder.record(&exchange, &result);
+        }
+        result
+    };
+
+    if let (Some((cassette, request)), Ok(response)) = (taped, &result) {
+        cassette.record(request, response);
+    }
+    result
+}
+
+// A replayed response's cookies go into the session's jar, as the server's would have
+fn replay_cookies(entry: &SessionEntry, response: &Response) {
+    let Ok(uri) = response.url.parse::<Uri>() else {
+        return;
     };
+    let cookies: Vec<HeaderValue> = response
+        .cookies
+        .iter()
+        .filter_map(|(name, value)| HeaderValue::from_str(&format!("{}={}", name, value)).ok())
+        .collect();
+    entry.state.jar.set_cookies(&mut cookies.iter(), &uri);
+}
+
+async fn make_request_inner(
+    entry: &SessionEntry,
+    options: RequestOptions,
+    mut exchange: Option<&mut Exchange>,
+) -> Result<Response> {
+    let client = &entry.client;
 
     let RequestOptions {
         url,
@@ -161,9 +1024,13 @@ async fn make_request_inner(options: RequestOptions) -> Result<Response> {
         body,
         timeout,
         disable_default_headers,
+        cache_mode,
         ..
     } = options;
 
+    // Rate limit queueing, the proxy resolver and the request itself share one timeout
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(timeout);
+
     let method = if method.is_empty() {
         "GET".to_string()
     } else {
@@ -180,36 +1047,168 @@ async fn make_request_inner(options: RequestOptions) -> Result<Response> {
         "DELETE" => client.delete(&url),
         "PATCH" => client.patch(&url),
         "HEAD" => client.head(&url),
-        _ => return Err(anyhow::anyhow!("Unsupported HTTP method: {}", method_upper)),
+        _ => {
+            let message = format!("Unsupported HTTP method: {}", method_upper);
+            return Err(Failure::new(ErrorCode::InvalidRequest, Phase::Setup, message).into());
+        }
+    };
+
+    // wreq appends client defaults to headers the request already has, so merge here instead:
+    // request headers replace session defaults, which replace the profile's. The profile's
+    // header order is applied when the request is sent.
+    let mut request_headers = if disable_default_headers {
+        // Leave out the profile's headers but keep the session's own
+        header_map(&entry.state.headers)?
+    } else {
+        entry.default_headers.clone()
     };
+    merge_headers(&mut request_headers, header_map(&headers)?);
+    let target = url.parse::<Uri>().ok();
 
-    // Apply custom headers
-    for (key, value) in headers.iter() {
-        request = request.header(key, value);
+    // Only GETs use the cache, and not ones that already carry validators of their own
+    let conditional = request_headers.contains_key(IF_NONE_MATCH)
+        || request_headers.contains_key(IF_MODIFIED_SINCE);
+    let cache = entry
+        .state
+        .cache
+        .as_ref()
+        .filter(|_| method_upper == "GET" && cache_mode != CacheMode::NoStore && !conditional);
+    let stored = match cache {
+        Some(cache) if cache_mode != CacheMode::Reload => cache.lookup(&url, &request_headers),
+        _ => None,
+    };
+    // Vary is matched against the headers as the caller sent them, without validators
+    let lookup_headers = cache.map(|_| request_headers.clone());
+    if let Some(stored) = &stored {
+        let usable = match cache_mode {
+            CacheMode::ForceCache | CacheMode::OnlyIfCached => true,
+            CacheMode::NoCache => false,
+            _ => stored.is_fresh(&request_headers),
+        };
+        if usable {
+            if let Some(exchange) = exchange.as_deref_mut() {
+                exchange.request(&request_headers, &[], body.as_deref());
+            }
+            return Ok(Response::from_cache(stored, url, CacheStatus::Hit));
+        }
+        stored.add_validators(&mut request_headers);
+    } else if cache_mode == CacheMode::OnlyIfCached {
+        let message = format!(
+            "No cached response for {} {} with cache mode 'only-if-cached'",
+            method_upper, url
+        );
+        return Err(Failure::new(ErrorCode::NotCached, Phase::Setup, message).into());
     }
 
-    // Disable default headers if requested to prevent emulation headers from being appended
-    if disable_default_headers {
-        request = request.default_headers(false);
+    if let Some(exchange) = exchange.as_deref_mut() {
+        let cookies = target
+            .as_ref()
+            .map(|target| entry.state.jar.cookies(target))
+            .unwrap_or_default();
+        exchange.request(&request_headers, &cookies, body.as_deref());
     }
+    request = request.headers(request_headers).default_headers(false);
+
+    // Wait for the session's and the host's limits. The wait counts against the timeout, as
+    // does reading a throttled body.
+    let host = target.as_ref().and_then(Uri::host);
+    let limiters: Vec<Arc<Limiter>> = std::iter::once(entry.state.limiter.clone())
+        .chain(host.and_then(host_limiter))
+        .collect();
+    let upload = body.as_ref().map_or(0, String::len);
+    let _slots = tokio::time::timeout_at(deadline, admit(&limiters, upload))
+        .await
+        .map_err(|_| {
+            let message = format!("Timed out after {}ms waiting for rate limits", timeout);
+            Failure::new(ErrorCode::Timeout, Phase::Queue, message)
+        })
+        .context(RequestTarget::new(&method_upper, &url, None))?;
 
     // Apply body if present
     if let Some(body) = body {
         request = request.body(body);
     }
 
-    // Apply timeout
-    request = request.timeout(Duration::from_millis(timeout));
+    // A pool or resolver picks the proxy per request; otherwise the client's fixed proxy is used
+    let pooled = entry.config.proxy_pool.as_ref().map(|pool| {
+        let proxy = pool.pick(host);
+        (pool, proxy)
+    });
+    let picked = match (&pooled, &entry.config.proxy_resolver) {
+        (Some((_, proxy)), _) => Some(Some(proxy.clone())),
+        (None, Some(resolver)) => {
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            let host = host.unwrap_or_default();
+            Some(resolver.resolve(&url, host, remaining).await?)
+        }
+        (None, None) => None,
+    };
+    if let Some(Some(proxy)) = &picked {
+        request = request.proxy(Proxy::all(proxy.as_str()).context("Failed to create proxy")?);
+    }
+    let proxy_used = match picked {
+        Some(proxy) => proxy.map(ProxySettings::new),
+        None => entry.config.proxy.clone(),
+    };
+    let proxy_url = proxy_used
+        .as_ref()
+        .zip(target.as_ref())
+        .and_then(|(proxy, target)| proxy.route(target));
+
+    // Apply what is left of the timeout
+    request = request.timeout(deadline.saturating_duration_since(tokio::time::Instant::now()));
 
     // Execute request
-    let response = request
-        .send()
-        .await
-        .with_context(|| format!("{} {}", method_upper, url))?;
+    if let Some(exchange) = exchange.as_deref_mut() {
+        exchange.sending();
+    }
+    let request_time = unix_seconds();
+    let result = request.send().await;
+
+    if let Some((pool, proxy)) = &pooled {
+        // Connection failures (tunnel errors included) and proxy auth rejections count against the proxy
+        let failed = match &result {
+            Ok(response) => response.status() == StatusCode::PROXY_AUTHENTICATION_REQUIRED,
+            Err(e) => e.is_connect(),
+        };
+        pool.report(proxy, !failed);
+    }
+
+    let response = match result {
+        Ok(response) => response,
+        Err(e) => {
+            let request_target = RequestTarget::new(&method_upper, &url, proxy_url.as_deref());
+            return Err(anyhow::Error::new(e).context(request_target));
+        }
+    };
+
+    if let Some(exchange) = exchange.as_deref_mut() {
+        exchange.responded(&response);
+    }
+    if let Some(Extension(history)) = response.extension::<Vec<History>>() {
+        for hop in history {
+            tracing::debug!(
+                status = hop.status().as_u16(),
+                from = %hop.previous(),
+                to = %hop.uri(),
+                "redirect followed"
+            );
+        }
+    }
+
+    // Held until the body is read, which is when wreq hands the connection back to the pool
+    let closing = response
+        .headers()
+        .get(wreq::header::CONNECTION)
+        .is_some_and(|value| value.as_bytes().eq_ignore_ascii_case(b"close"));
+    let _connection = response
+        .local_addr()
+        .and_then(|addr| entry.connections.checkout(response.uri(), addr, closing));
 
     // Extract response data
     let status = response.status().as_u16();
     let final_url = response.uri().to_string();
+    let redirected = target.as_ref() != Some(response.uri());
 
     // Extract headers
     let mut response_headers = IndexMap::new();
@@ -226,55 +1225,322 @@ async fn make_request_inner(options: RequestOptions) -> Result<Response> {
     }
 
     // Get body
-    let body = response
-        .text()
+    let body = read_body(response, &limiters)
         .await
         .context("Failed to read response body")?;
 
+    // A successful unsafe request may have changed what is stored for the URL (RFC 9111 4.4)
+    let unsafe_method = !matches!(method_upper.as_str(), "GET" | "HEAD");
+    let invalidated = entry
+        .state
+        .cache
+        .as_ref()
+        .filter(|_| unsafe_method && status < 400);
+    if let Some(cache) = invalidated {
+        cache.invalidate(&url);
+    }
+
+    let mut cache_status = None;
+    if let (Some(cache), Some(lookup_headers)) = (cache, &lookup_headers) {
+        if let (304, Some(stored)) = (status, stored) {
+            let refreshed = cache.refresh(&url, stored, &response_headers, request_time);
+            let mut response = Response::from_cache(&refreshed, url, CacheStatus::Revalidated);
+            response.cookies = cookies;
+            response.proxy = proxy_url;
+            return Ok(response);
+        }
+        // Redirects are followed, and what arrives belongs to the last URL, not this one
+        if !redirected {
+            let stored = CachedResponse::new(status, &response_headers, &body, request_time);
+            cache.store(&url, lookup_headers, stored);
+        }
+        cache_status = Some(CacheStatus::Miss);
+    }
+
     Ok(Response {
         status,
         headers: response_headers,
         body,
         cookies,
         url: final_url,
+        proxy: proxy_url,
+        cache_status,
     })
 }
 
-fn build_client(config: &SessionConfig) -> Result<HttpClient> {
+impl Response {
+    // A stored response, with its current age when served without asking the server
+    fn from_cache(stored: &CachedResponse, url: String, cache_status: CacheStatus) -> Self {
+        let mut headers = stored.headers.clone();
+        if cache_status == CacheStatus::Hit {
+            headers.insert("age".to_string(), stored.age().to_string());
+        }
+        Self {
+            status: stored.status,
+            headers,
+            body: stored.body.clone(),
+            cookies: IndexMap::new(),
+            url,
+            proxy: None,
+            cache_status: Some(cache_status),
+        }
+    }
+}
+
+// Wait until every limiter admits the request and its `upload` body bytes
+async fn admit(limiters: &[Arc<Limiter>], upload: usize) -> Vec<Slot> {
+    let mut slots = Vec::with_capacity(limiters.len());
+    for limiter in limiters {
+        slots.push(limiter.admit().await);
+        limiter.transfer(upload).await;
+    }
+    slots
+}
+
+// Read the body as text, no faster than the byte rate limits allow
+async fn read_body(
+    mut response: wreq::Response,
+    limiters: &[Arc<Limiter>],
+) -> wreq::Result<String> {
+    if !limiters.iter().any(|limiter| limiter.limits_bytes()) {
+        return response.text().await;
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        for limiter in limiters {
+            limiter.transfer(chunk.len()).await;
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    // Decoded by wreq like any other body, honouring the charset in Content-Type
+    let mut buffered = http::Response::new(body);
+    *buffered.headers_mut() = response.headers().clone();
+    wreq::Response::from(buffered).text().await
+}
+
+/// Cancels a request when JS aborts it, by the id JS gave it. Registered before the request
+/// is spawned, so an abort right after the call is not missed.
+pub struct Cancellation {
+    request_id: String,
+    cancelled: oneshot::Receiver<()>,
+}
+
+impl Cancellation {
+    pub fn register(request_id: String) -> Self {
+        let (cancel, cancelled) = oneshot::channel();
+        CANCELLABLE
+            .lock()
+            .unwrap()
+            .insert(request_id.clone(), cancel);
+        Self {
+            request_id,
+            cancelled,
+        }
+    }
+
+    /// Run `request` until it finishes or is cancelled. Dropping it on cancellation takes
+    /// it out of any rate limit queue it is waiting in.
+    pub async fn run<T>(mut self, request: impl Future<Output = Result<T>>) -> Result<T> {
+        tokio::select! {
+            result = request => result,
+            _ = &mut self.cancelled => {
+                Err(Failure::new(ErrorCode::Aborted, Phase::Request, "Request was aborted").into())
+            }
+        }
+    }
+}
+
+impl Drop for Cancellation {
+    fn drop(&mut self) {
+        CANCELLABLE.lock().unwrap().remove(&self.request_id);
+    }
+}
+
+pub fn cancel_request(request_id: &str) {
+    if let Some(cancel) = CANCELLABLE.lock().unwrap().remove(request_id) {
+        let _ = cancel.send(());
+    }
+}
+
+// Returns the client with the default headers it sends: the profile's, with the session's merged in.
+// Without `cookies` the client neither stores nor sends cookies from the session's jar.
+fn build_client(
+    config: &SessionConfig,
+    state: &SessionState,
+    cookies: bool,
+) -> Result<(HttpClient, HeaderMap)> {
+    let mut emulation = config.emulation.to_emulation()?;
+    merge_headers(emulation.headers_mut(), header_map(&state.headers)?);
+    let default_headers = emulation.headers_mut().clone();
+
+    // Redirect history lets recorders log each redirect followed; the resolver's typed
+    // errors let a failed lookup be classified as DNS
     let mut client_builder = HttpClient::builder()
-        .emulation(config.emulation.clone())
-        .cookie_store(true);
+        .emulation(emulation)
+        .history(true)
+        .dns_resolver(SystemResolver);
+    if cookies {
+        client_builder = client_builder.cookie_provider(state.jar.clone());
+    }
+    client_builder = config.connection_pool.configure(client_builder);
 
-    if let Some(proxy_url) = config.proxy.as_deref() {
-        let proxy = Proxy::all(proxy_url).context("Failed to create proxy")?;
-        client_builder = client_builder.proxy(proxy);
+    if let Some(proxy) = &config.proxy {
+        client_builder = proxy.configure(client_builder, false)?;
+    }
+    // Requests the resolver sends direct must not fall back to the system proxy
+    if config.proxy_resolver.is_some() {
+        client_builder = client_builder.no_proxy();
     }
 
-    client_builder
+    let client = client_builder
         .build()
-        .context("Failed to build HTTP client")
+        .context("Failed to build HTTP client")?;
+
+    Ok((client, default_headers))
+}
+
+fn header_map(headers: &IndexMap<String, String>) -> Result<HeaderMap> {
+    let mut map = HeaderMap::with_capacity(headers.len());
+    for (name, value) in headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .with_context(|| format!("Invalid header name '{}'", name))?;
+        let value = HeaderValue::from_str(value)
+            .with_context(|| format!("Invalid value for header '{}'", name))?;
+        map.append(name, value);
+    }
+    Ok(map)
 }
 
-fn emulation_label(emulation: &Emulation) -> String {
-    match serde_json::to_value(emulation) {
-        Ok(Value::String(label)) => label,
-        _ => "chrome_142".to_string(),
+// Every name in `overrides` replaces all of its values in `headers`. Replaced headers keep
+// their place, so they still sort into the profile's position for that header.
+fn merge_headers(headers: &mut HeaderMap, overrides: HeaderMap) {
+    let mut current = None;
+    for (name, value) in overrides {
+        match name {
+            Some(name) => {
+                headers.insert(name.clone(), value);
+                current = Some(name);
+            }
+            None => {
+                if let Some(name) = &current {
+                    headers.append(name.clone(), value);
+                }
+            }
+        }
     }
 }
 
-pub fn create_managed_session(session_id: String, emulation: Emulation, proxy: Option<String>) -> Result<String> {
-    let config = SessionConfig::new(emulation, proxy);
-    SESSION_MANAGER.create_session(session_id, config)
+pub fn create_managed_session(
+    session_id: String,
+    config: SessionConfig,
+    never_expire: bool,
+    headers: IndexMap<String, String>,
+    extras: SessionExtras,
+) -> Result<String> {
+    SESSION_MANAGER.create_session(session_id, config, never_expire, headers, extras)
+}
+
+pub fn session_limits() -> SessionLimits {
+    SESSION_MANAGER.limits()
+}
+
+pub fn configure_session_limits(limits: SessionLimits) {
+    SESSION_MANAGER.configure(limits);
+}
+
+pub fn set_eviction_handler(handler: Option<EvictionHandler>) {
+    SESSION_MANAGER.set_eviction_handler(handler);
+}
+
+pub fn reset_managed_session(session_id: &str, mode: ResetMode) -> Result<()> {
+    SESSION_MANAGER.reset_session(session_id, mode)
 }
 
-pub fn clear_managed_session(session_id: &str) -> Result<()> {
-    SESSION_MANAGER.clear_session(session_id)
+pub fn reconfigure_managed_session(session_id: &str, update: SessionUpdate) -> Result<()> {
+    SESSION_MANAGER.reconfigure_session(session_id, update)
 }
 
 pub fn drop_managed_session(session_id: &str) {
     SESSION_MANAGER.drop_session(session_id);
 }
 
+pub fn managed_session_info(session_id: &str) -> Option<SessionInfo> {
+    SESSION_MANAGER.session_info(session_id)
+}
+
+/// Open, idle and in-use connections per origin of a session's current client
+pub fn observed_managed_connections(session_id: &str) -> Result<Vec<ObservedOrigin>> {
+    SESSION_MANAGER.observed_connections(session_id)
+}
+
+pub fn list_managed_sessions() -> Vec<SessionInfo> {
+    SESSION_MANAGER.list_sessions()
+}
+
+pub fn snapshot_managed_session(
+    session_id: &str,
+    defaults: SnapshotDefaults,
+) -> Result<SessionSnapshot> {
+    SESSION_MANAGER.snapshot_session(session_id, defaults)
+}
+
+/// Recreate a session from a snapshot, under `session_id` or the id it was saved with.
+/// Returns the session id and the pool rebuilt for it, if the session used one.
+pub fn restore_managed_session(
+    snapshot: &SessionSnapshot,
+    session_id: Option<String>,
+) -> Result<(String, Option<Arc<ProxyPool>>)> {
+    let os = parse_os(&snapshot.os).ok_or_else(|| {
+        anyhow::Error::new(Failure::new(
+            ErrorCode::Snapshot,
+            Phase::Setup,
+            format!("Unknown operating system '{}' in snapshot", snapshot.os),
+        ))
+    })?;
+    let settings = EmulationSettings {
+        os,
+        skip_http2: snapshot.skip_http2,
+        skip_headers: snapshot.skip_headers,
+    };
+    // Custom profiles are saved by name and must be registered again before restoring
+    let emulation = resolve_profile(&snapshot.browser)?.with_settings(settings);
+
+    // Pools are restored with their settings; health and counters start over
+    let proxy_pool = match &snapshot.proxy_pool {
+        Some(config) => Some(Arc::new(ProxyPool::new(config.clone())?)),
+        None => None,
+    };
+
+    let config = SessionConfig::new(
+        emulation,
+        snapshot.proxy_settings(),
+        proxy_pool.clone(),
+        None,
+        snapshot.connection_pool.unwrap_or_default(),
+        snapshot.rate_limits.unwrap_or_default(),
+        snapshot.cache.clone(),
+    );
+    let session_id = session_id.unwrap_or_else(|| snapshot.session_id.clone());
+    let session_id = SESSION_MANAGER.restore_session(session_id, config, snapshot)?;
+    Ok((session_id, proxy_pool))
+}
+
+fn open_cache(config: &SessionConfig) -> Result<Option<Arc<HttpCache>>> {
+    match &config.cache {
+        Some(settings) => Ok(Some(Arc::new(HttpCache::new(settings.clone())?))),
+        None => Ok(None),
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
 pub fn generate_session_id() -> String {
     Uuid::new_v4().to_string()
 }
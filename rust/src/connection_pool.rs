@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use wreq::{ClientBuilder, Uri};
+
+/// Connection pool limits of a session's client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolSettings {
+    /// Idle connections kept per origin; `None` for no limit
+    pub max_idle_per_host: Option<usize>,
+    /// How long an idle connection is kept, in milliseconds; `None` keeps it until the
+    /// server closes it
+    pub idle_timeout: Option<u64>,
+    /// Origins whose idle connections are kept at once. Past it, the idle connections
+    /// of the least recently used origin are closed. `None` for no limit.
+    pub max_size: Option<u32>,
+}
+
+impl Default for PoolSettings {
+    // wreq's own defaults
+    fn default() -> Self {
+        Self {
+            max_idle_per_host: None,
+            idle_timeout: Some(90_000),
+            max_size: None,
+        }
+    }
+}
+
+impl PoolSettings {
+    pub fn configure(&self, builder: ClientBuilder) -> ClientBuilder {
+        builder
+            .pool_max_idle_per_host(self.max_idle_per_host.unwrap_or(usize::MAX))
+            .pool_idle_timeout(self.idle_timeout.map(Duration::from_millis))
+            .pool_max_size(self.max_size.unwrap_or(0))
+    }
+}
+
+/// Connections of one origin as `ConnectionObserver::observed` saw them; not wreq's pool state
+#[derive(Debug, Clone)]
+pub struct ObservedOrigin {
+    /// `scheme://host:port`
+    pub origin: String,
+    pub observed: usize,
+    pub observed_idle: usize,
+    pub observed_in_use: usize,
+}
+
+/// Remembers the connections a client's responses arrived on, since wreq does not expose its
+/// pool. Connections are told apart by their local address. Ones the server closes are not
+/// seen, so an idle one is remembered until the idle limits would have dropped it.
+#[derive(Debug)]
+pub struct ConnectionObserver {
+    settings: PoolSettings,
+    origins: Mutex<HashMap<String, HashMap<SocketAddr, TrackedConnection>>>,
+}
+
+#[derive(Debug)]
+struct TrackedConnection {
+    // Requests whose responses are still being read; HTTP/2 serves several at once
+    requests: usize,
+    last_used: Instant,
+}
+
+/// A request being served on a tracked connection; the connection turns idle, or is
+/// forgotten if the server said it closes it, when the last of them is dropped
+pub struct ConnectionUse {
+    tracker: Arc<ConnectionObserver>,
+    origin: String,
+    local_addr: SocketAddr,
+    closing: bool,
+}
+
+impl ConnectionObserver {
+    pub fn new(settings: PoolSettings) -> Self {
+        Self {
+            settings,
+            origins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Mark the connection at `local_addr` as serving a request to `url`
+    pub fn checkout(
+        self: &Arc<Self>,
+        url: &Uri,
+        local_addr: SocketAddr,
+        closing: bool,
+    ) -> Option<ConnectionUse> {
+        let origin = origin(url)?;
+        let mut origins = self.origins.lock().unwrap();
+        let connections = origins.entry(origin.clone()).or_default();
+        let connection = connections.entry(local_addr).or_insert(TrackedConnection {
+            requests: 0,
+            last_used: Instant::now(),
+        });
+        connection.requests += 1;
+
+        Some(ConnectionUse {
+            tracker: self.clone(),
+            origin,
+            local_addr,
+            closing,
+        })
+    }
+
+    /// Observed connections per origin, most recently used origin first
+    pub fn observed(&self) -> Vec<ObservedOrigin> {
+        let mut origins = self.origins.lock().unwrap();
+        self.prune(&mut origins);
+
+        let mut stats: Vec<(Instant, ObservedOrigin)> = origins
+            .iter()
+            .map(|(origin, connections)| {
+                let in_use = connections.values().filter(|c| c.requests > 0).count();
+                let last_used = connections.values().map(|c| c.last_used).max();
+                let stats = ObservedOrigin {
+                    origin: origin.clone(),
+                    observed: connections.len(),
+                    observed_idle: connections.len() - in_use,
+                    observed_in_use: in_use,
+                };
+                (last_used.unwrap_or_else(Instant::now), stats)
+            })
+            .collect();
+        stats.sort_by(|a, b| b.0.cmp(&a.0));
+        stats.into_iter().map(|(_, stats)| stats).collect()
+    }
+
+    // Forget idle connections the pool has closed by now under its limits
+    fn prune(&self, origins: &mut HashMap<String, HashMap<SocketAddr, TrackedConnection>>) {
+        let idle_timeout = self.settings.idle_timeout.map(Duration::from_millis);
+        let max_idle = self.settings.max_idle_per_host.unwrap_or(usize::MAX);
+
+        for connections in origins.values_mut() {
+            connections.retain(|_, connection| {
+                connection.requests > 0
+                    || idle_timeout.is_none_or(|timeout| connection.last_used.elapsed() < timeout)
+            });
+
+            let mut idle: Vec<(SocketAddr, Instant)> = connections
+                .iter()
+                .filter(|(_, connection)| connection.requests == 0)
+                .map(|(addr, connection)| (*addr, connection.last_used))
+                .collect();
+            idle.sort_by(|a, b| b.1.cmp(&a.1));
+            for (addr, _) in idle.into_iter().skip(max_idle) {
+                connections.remove(&addr);
+            }
+        }
+
+        if let Some(max_size) = self.settings.max_size {
+            let mut idle_origins: Vec<(String, Instant)> = origins
+                .iter()
+                .filter(|(_, connections)| connections.values().all(|c| c.requests == 0))
+                .filter_map(|(origin, connections)| {
+                    let last_used = connections.values().map(|c| c.last_used).max()?;
+                    Some((origin.clone(), last_used))
+                })
+                .collect();
+            idle_origins.sort_by(|a, b| b.1.cmp(&a.1));
+            for (origin, _) in idle_origins.into_iter().skip(max_size as usize) {
+                origins.remove(&origin);
+            }
+        }
+
+        origins.retain(|_, connections| !connections.is_empty());
+    }
+}
+
+impl Drop for ConnectionUse {
+    fn drop(&mut self) {
+        let mut origins = self.tracker.origins.lock().unwrap();
+        let Some(connections) = origins.get_mut(&self.origin) else {
+            return;
+        };
+        if let Some(connection) = connections.get_mut(&self.local_addr) {
+            connection.requests = connection.requests.saturating_sub(1);
+            connection.last_used = Instant::now();
+            if self.closing && connection.requests == 0 {
+                connections.remove(&self.local_addr);
+            }
+        }
+        if connections.is_empty() {
+            origins.remove(&self.origin);
+        }
+    }
+}
+
+fn origin(url: &Uri) -> Option<String> {
+    let scheme = url.scheme_str()?;
+    let host = url.host()?;
+    let port = url
+        .port_u16()
+        .unwrap_or(if scheme == "https" { 443 } else { 80 });
+    Some(format!("{}://{}:{}", scheme, host, port))
+}
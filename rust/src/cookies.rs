@@ -0,0 +1,161 @@
+use cookie::time::OffsetDateTime;
+use cookie::{Cookie as RawCookie, SameSite};
+use serde::{Deserialize, Serialize};
+use wreq::cookie::{CookieStore, Jar};
+use wreq::header::HeaderValue;
+use wreq::Uri;
+
+// Any valid URI will do when importing: every exported cookie carries its own domain and path
+const IMPORT_URI: &str = "http://localhost/";
+
+/// Cookie jar of a managed session.
+///
+/// Wraps wreq's `Jar`, but fills in the domain, path and absolute expiry of every
+/// cookie it stores. The inner jar keys host-only cookies by the request host without
+/// recording it on the cookie, so without this the jar could not be exported.
+#[derive(Default)]
+pub struct SessionCookies {
+    jar: Jar,
+}
+
+/// A stored cookie in the session snapshot format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CookieRecord {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    pub path: String,
+    #[serde(default)]
+    pub secure: bool,
+    #[serde(default)]
+    pub http_only: bool,
+    /// "strict", "lax" or "none"; absent when the server did not send SameSite
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub same_site: Option<String>,
+    /// Milliseconds since the Unix epoch; absent for cookies that last as long as the session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<i64>,
+}
+
+impl SessionCookies {
+    pub fn import(records: &[CookieRecord]) -> Self {
+        let cookies = Self::default();
+        for record in records {
+            cookies.jar.add_cookie(record.to_cookie(), IMPORT_URI);
+        }
+        cookies
+    }
+
+    pub fn export(&self) -> Vec<CookieRecord> {
+        self.jar
+            .get_all()
+            .filter_map(|cookie| CookieRecord::from_cookie(&RawCookie::from(cookie)))
+            .collect()
+    }
+
+    pub fn count(&self) -> usize {
+        self.jar.get_all().count()
+    }
+
+    pub fn clear(&self) {
+        self.jar.clear();
+    }
+}
+
+impl CookieStore for SessionCookies {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, uri: &Uri) {
+        let cookies = cookie_headers
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(|value| RawCookie::parse(value).ok())
+            .map(|cookie| pin_scope(cookie.into_owned(), uri));
+
+        for cookie in cookies {
+            self.jar.add_cookie(cookie, uri);
+        }
+    }
+
+    fn cookies(&self, uri: &Uri) -> Vec<HeaderValue> {
+        self.jar.cookies(uri)
+    }
+}
+
+impl CookieRecord {
+    fn from_cookie(cookie: &RawCookie<'_>) -> Option<Self> {
+        let same_site = cookie.same_site().map(|same_site| match same_site {
+            SameSite::Strict => "strict".to_string(),
+            SameSite::Lax => "lax".to_string(),
+            SameSite::None => "none".to_string(),
+        });
+
+        Some(Self {
+            name: cookie.name().to_string(),
+            value: cookie.value().to_string(),
+            domain: cookie.domain()?.to_string(),
+            path: cookie.path()?.to_string(),
+            secure: cookie.secure().unwrap_or(false),
+            http_only: cookie.http_only().unwrap_or(false),
+            same_site,
+            expires: cookie
+                .expires_datetime()
+                .map(|expires| (expires.unix_timestamp_nanos() / 1_000_000) as i64),
+        })
+    }
+
+    fn to_cookie(&self) -> RawCookie<'static> {
+        let mut builder = RawCookie::build((self.name.clone(), self.value.clone()))
+            .domain(self.domain.clone())
+            .path(self.path.clone())
+            .secure(self.secure)
+            .http_only(self.http_only);
+
+        let same_site = self.same_site.as_deref().map(str::to_ascii_lowercase);
+        match same_site.as_deref() {
+            Some("strict") => builder = builder.same_site(SameSite::Strict),
+            Some("lax") => builder = builder.same_site(SameSite::Lax),
+            Some("none") => builder = builder.same_site(SameSite::None),
+            _ => {}
+        }
+
+        let expires = self.expires.and_then(|millis| {
+            OffsetDateTime::from_unix_timestamp_nanos(i128::from(millis) * 1_000_000).ok()
+        });
+        if let Some(expires) = expires {
+            builder = builder.expires(expires);
+        }
+
+        builder.build()
+    }
+}
+
+// Record the scope and expiry the jar would otherwise derive from the request
+fn pin_scope(mut cookie: RawCookie<'static>, uri: &Uri) -> RawCookie<'static> {
+    let host = uri.host().filter(|_| cookie.domain().is_none());
+    if let Some(host) = host {
+        cookie.set_domain(host.to_string());
+    }
+
+    if cookie.path().is_none() {
+        cookie.set_path(default_path(uri).to_string());
+    }
+
+    // Max-Age wins over Expires (RFC 6265 section 5.3); keep zero as-is so the jar deletes the cookie
+    let expires = cookie
+        .max_age()
+        .filter(|age| age.is_positive())
+        .and_then(|age| OffsetDateTime::now_utc().checked_add(age));
+    if let Some(expires) = expires {
+        cookie.set_expires(expires);
+    }
+
+    cookie
+}
+
+// Default cookie path, RFC 6265 section 5.1.4
+fn default_path(uri: &Uri) -> &str {
+    let path = uri.path();
+    match path.rfind('/') {
+        Some(pos) if path.starts_with('/') && pos > 0 => &path[..pos],
+        _ => "/",
+    }
+}
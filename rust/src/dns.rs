@@ -0,0 +1,53 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+use wreq::dns::{Addrs, Name, Resolve, Resolving};
+
+/// A host name that could not be resolved. wreq keeps it in the source chain of its
+/// connect error, so a failed lookup is told apart by type rather than by message.
+#[derive(Debug)]
+pub struct DnsError {
+    host: String,
+    source: io::Error,
+}
+
+impl fmt::Display for DnsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "could not resolve '{}'", self.host)
+    }
+}
+
+impl StdError for DnsError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Resolves with the system's `getaddrinfo` on the blocking pool, as wreq does by default,
+/// failing with a [`DnsError`]
+#[derive(Clone, Copy, Default)]
+pub struct SystemResolver;
+
+impl Resolve for SystemResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let lookup = host.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                (lookup.as_str(), 0)
+                    .to_socket_addrs()
+                    .map(|addrs| addrs.collect::<Vec<SocketAddr>>())
+            })
+            .await
+            .unwrap_or_else(|error| Err(io::Error::other(error)));
+
+            match result {
+                Ok(addrs) => Ok(Box::new(addrs.into_iter()) as Addrs),
+                Err(source) => {
+                    Err(Box::new(DnsError { host, source }) as Box<dyn StdError + Send + Sync>)
+                }
+            }
+        })
+    }
+}
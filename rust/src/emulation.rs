@@ -0,0 +1,804 @@
+use crate::generated_profiles::{Engine, Platform, ProfileInfo, BROWSER_PROFILES};
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use wreq::header::{HeaderMap, HeaderName, HeaderValue, OrigHeaderMap};
+use wreq::http2::{
+    Http2Options, Priorities, Priority, PseudoId, PseudoOrder, SettingId, SettingsOrder,
+    StreamDependency,
+};
+use wreq::tls::{
+    AlpnProtocol, AlpsProtocol, CertificateCompressionAlgorithm, ExtensionType, TlsOptions,
+    TlsVersion,
+};
+use wreq::EmulationFactory;
+use wreq_util::{Emulation, EmulationOS, EmulationOption};
+
+// Registry of user-defined profiles, keyed by the name used as `browser`
+static CUSTOM_PROFILES: Lazy<RwLock<HashMap<String, Arc<CustomProfile>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+// `{group}_latest` resolves to the newest profile of that group, e.g. `safari_ios_latest`
+const LATEST_SUFFIX: &str = "_latest";
+
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Emulation selected for a request, session or WebSocket
+#[derive(Debug, Clone)]
+pub struct EmulationProfile {
+    source: ProfileSource,
+    settings: EmulationSettings,
+}
+
+#[derive(Debug, Clone)]
+enum ProfileSource {
+    Builtin(&'static ProfileInfo),
+    Custom(Arc<CustomProfile>),
+}
+
+/// Operating system and feature toggles applied on top of a profile
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EmulationSettings {
+    pub os: EmulationOS,
+    pub skip_http2: bool,
+    pub skip_headers: bool,
+}
+
+impl EmulationSettings {
+    /// Operating system name, e.g. `windows`
+    pub fn os_name(&self) -> String {
+        os_label(self.os)
+    }
+}
+
+impl EmulationProfile {
+    pub fn builtin(info: &'static ProfileInfo) -> Self {
+        Self {
+            source: ProfileSource::Builtin(info),
+            settings: EmulationSettings::default(),
+        }
+    }
+
+    pub fn custom(profile: Arc<CustomProfile>) -> Self {
+        Self {
+            source: ProfileSource::Custom(profile),
+            settings: EmulationSettings::default(),
+        }
+    }
+
+    pub fn with_settings(mut self, settings: EmulationSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Name the profile was selected by
+    pub fn name(&self) -> String {
+        match &self.source {
+            ProfileSource::Builtin(info) => info.name.to_string(),
+            ProfileSource::Custom(profile) => profile.name.clone(),
+        }
+    }
+
+    /// Whether both were selected from the same profile definition; a custom profile
+    /// registered again under its name is a different one
+    pub fn same_definition(&self, other: &EmulationProfile) -> bool {
+        match (&self.source, &other.source) {
+            (ProfileSource::Builtin(a), ProfileSource::Builtin(b)) => a.name == b.name,
+            (ProfileSource::Custom(a), ProfileSource::Custom(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    pub fn settings(&self) -> EmulationSettings {
+        self.settings
+    }
+
+    /// Profile name plus any non-default OS or skip flags, e.g. `chrome_142@windows+skip_http2`
+    pub fn label(&self) -> String {
+        let mut label = self.name();
+        if self.settings.os != EmulationOS::default() {
+            label.push('@');
+            label.push_str(&os_label(self.settings.os));
+        }
+        if self.settings.skip_http2 {
+            label.push_str("+skip_http2");
+        }
+        if self.settings.skip_headers {
+            label.push_str("+skip_headers");
+        }
+        label
+    }
+
+    /// Build the wreq emulation applied to a client
+    pub fn to_emulation(&self) -> Result<wreq::Emulation> {
+        match &self.source {
+            ProfileSource::Builtin(info) => {
+                Ok(emulation_option(info.emulation, &self.settings).emulation())
+            }
+            ProfileSource::Custom(profile) => profile.definition.build(&self.settings),
+        }
+    }
+}
+
+/// A registered custom profile
+#[derive(Debug)]
+pub struct CustomProfile {
+    name: String,
+    definition: ProfileDefinition,
+    base: Option<&'static ProfileInfo>,
+}
+
+/// Metadata for a built-in or custom profile, as reported by `getProfiles`
+#[derive(Debug, Clone)]
+pub struct ProfileEntry {
+    pub name: String,
+    pub family: String,
+    pub version: Option<String>,
+    /// Name without the version, e.g. `safari_ios` for `safari_ios_17.2`
+    pub group: String,
+    pub platform: Option<Platform>,
+    pub private: bool,
+    pub engine: Option<Engine>,
+}
+
+impl From<&ProfileInfo> for ProfileEntry {
+    fn from(info: &ProfileInfo) -> Self {
+        Self {
+            name: info.name.to_string(),
+            family: info.family.to_string(),
+            version: Some(info.version.to_string()),
+            group: info.group.to_string(),
+            platform: Some(info.platform),
+            private: info.private,
+            engine: Some(info.engine),
+        }
+    }
+}
+
+/// Register (or replace) a custom profile from its JSON definition. Sessions already built
+/// from a replaced profile hold on to the old definition.
+pub fn register_profile(name: &str, definition: &str) -> Result<()> {
+    let name = name.trim();
+    if name.is_empty() {
+        anyhow::bail!("Profile name must not be empty");
+    }
+
+    if resolve_builtin(name).is_some() {
+        anyhow::bail!("Profile name '{}' is reserved by a built-in profile", name);
+    }
+
+    let definition: ProfileDefinition = serde_json::from_str(definition)
+        .with_context(|| format!("Invalid definition for profile '{}'", name))?;
+
+    // Build once up front so invalid values are reported at registration time
+    definition
+        .build(&EmulationSettings::default())
+        .with_context(|| format!("Invalid definition for profile '{}'", name))?;
+
+    let base = definition.extends.as_deref().and_then(resolve_builtin);
+
+    let profile = Arc::new(CustomProfile {
+        name: name.to_string(),
+        definition,
+        base,
+    });
+
+    CUSTOM_PROFILES
+        .write()
+        .unwrap()
+        .insert(name.to_string(), profile);
+    Ok(())
+}
+
+/// Look up a custom profile by name
+pub fn custom_profile(name: &str) -> Option<Arc<CustomProfile>> {
+    CUSTOM_PROFILES.read().unwrap().get(name).cloned()
+}
+
+/// Built-in profiles followed by registered custom profiles (sorted by name)
+pub fn available_profiles() -> Vec<ProfileEntry> {
+    let mut profiles: Vec<ProfileEntry> = BROWSER_PROFILES.iter().map(ProfileEntry::from).collect();
+
+    let mut custom: Vec<Arc<CustomProfile>> =
+        CUSTOM_PROFILES.read().unwrap().values().cloned().collect();
+    custom.sort_by(|a, b| a.name.cmp(&b.name));
+
+    // Custom profiles inherit platform and engine from the profile they extend
+    profiles.extend(custom.into_iter().map(|profile| ProfileEntry {
+        name: profile.name.clone(),
+        family: "custom".to_string(),
+        version: None,
+        group: profile.name.clone(),
+        platform: profile.base.map(|base| base.platform),
+        private: profile.base.is_some_and(|base| base.private),
+        engine: profile.base.map(|base| base.engine),
+    }));
+
+    profiles
+}
+
+/// Failure to resolve a `browser` name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileError {
+    /// No built-in profile, alias or custom profile has this name
+    Unknown {
+        name: String,
+        suggestions: Vec<String>,
+    },
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::Unknown { name, suggestions } if suggestions.is_empty() => {
+                write!(f, "Unknown browser profile '{}'", name)
+            }
+            ProfileError::Unknown { name, suggestions } => write!(
+                f,
+                "Unknown browser profile '{}'. Did you mean: {}?",
+                name,
+                suggestions.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+/// Resolve a custom profile, built-in profile or `_latest` alias by name
+pub fn resolve_profile(name: &str) -> Result<EmulationProfile, ProfileError> {
+    if let Some(profile) = custom_profile(name) {
+        return Ok(EmulationProfile::custom(profile));
+    }
+
+    if let Some(info) = resolve_builtin(name) {
+        return Ok(EmulationProfile::builtin(info));
+    }
+
+    Err(ProfileError::Unknown {
+        name: name.to_string(),
+        suggestions: suggest_profiles(name),
+    })
+}
+
+/// Look up a built-in profile by exact name or `_latest` alias in the generated profile table
+pub fn resolve_builtin(name: &str) -> Option<&'static ProfileInfo> {
+    match name.strip_suffix(LATEST_SUFFIX) {
+        Some(group) => latest_in_group(group),
+        None => BROWSER_PROFILES.iter().find(|p| p.name == name),
+    }
+}
+
+/// Profile used when no `browser` is given: the newest desktop Chrome
+pub fn default_profile() -> &'static ProfileInfo {
+    latest_in_group("chrome").expect("generated profile table contains no Chrome profiles")
+}
+
+/// All `_latest` aliases, in profile table order
+pub fn profile_aliases() -> Vec<String> {
+    let mut aliases: Vec<String> = Vec::new();
+    for info in BROWSER_PROFILES {
+        let alias = format!("{}{}", info.group, LATEST_SUFFIX);
+        if !aliases.contains(&alias) {
+            aliases.push(alias);
+        }
+    }
+    aliases
+}
+
+fn latest_in_group(group: &str) -> Option<&'static ProfileInfo> {
+    BROWSER_PROFILES
+        .iter()
+        .filter(|p| p.group == group)
+        .max_by(|a, b| compare_versions(a.version, b.version))
+}
+
+// Compare dotted versions numerically, so "17.4.1" > "17.2" and "142" > "99"
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parse = |version: &str| -> Vec<u32> {
+        version
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    parse(a).cmp(&parse(b))
+}
+
+// Closest known names by edit distance; the `_latest` alias of an exact group or
+// family name always comes first
+fn suggest_profiles(name: &str) -> Vec<String> {
+    let needle = name.to_ascii_lowercase();
+    let threshold = (needle.len() / 3).max(2);
+
+    let mut candidates: Vec<String> = BROWSER_PROFILES
+        .iter()
+        .map(|p| p.name.to_string())
+        .collect();
+    candidates.extend(profile_aliases());
+    candidates.extend(CUSTOM_PROFILES.read().unwrap().keys().cloned());
+
+    let mut scored: Vec<(usize, String)> = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let distance = if candidate == format!("{}{}", needle, LATEST_SUFFIX) {
+                0
+            } else {
+                edit_distance(&needle, &candidate.to_ascii_lowercase())
+            };
+            (distance <= threshold).then_some((distance, candidate))
+        })
+        .collect();
+
+    // Ties prefer the newer-looking name, e.g. chrome_142 before chrome_123
+    scored.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(&a.1)));
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+// Levenshtein distance over bytes (profile names are ASCII)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.as_bytes();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.bytes().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// Parse an operating system name (`windows`, `macos`, `linux`, `android`, `ios`)
+pub fn parse_os(name: &str) -> Option<EmulationOS> {
+    serde_json::from_value(Value::String(name.to_ascii_lowercase())).ok()
+}
+
+fn os_label(os: EmulationOS) -> String {
+    match serde_json::to_value(os) {
+        Ok(Value::String(label)) => label,
+        _ => format!("{:?}", os).to_ascii_lowercase(),
+    }
+}
+
+fn emulation_option(emulation: Emulation, settings: &EmulationSettings) -> EmulationOption {
+    EmulationOption::builder()
+        .emulation(emulation)
+        .emulation_os(settings.os)
+        .skip_http2(settings.skip_http2)
+        .skip_headers(settings.skip_headers)
+        .build()
+}
+
+// JSON definition of a custom profile. Every section is optional; anything
+// left out is inherited from `extends` (or wreq defaults when absent).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ProfileDefinition {
+    extends: Option<String>,
+    tls: Option<TlsDefinition>,
+    http2: Option<Http2Definition>,
+    headers: Option<HeaderList>,
+    header_order: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum HeaderList {
+    Tuples(Vec<(String, String)>),
+    Object(IndexMap<String, String>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum StringList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl StringList {
+    fn joined(self) -> Cow<'static, str> {
+        match self {
+            StringList::One(value) => Cow::Owned(value),
+            StringList::Many(values) => Cow::Owned(values.join(":")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct TlsDefinition {
+    alpn_protocols: Option<Vec<String>>,
+    alps_protocols: Option<Vec<String>>,
+    alps_use_new_codepoint: Option<bool>,
+    session_ticket: Option<bool>,
+    min_version: Option<String>,
+    max_version: Option<String>,
+    pre_shared_key: Option<bool>,
+    enable_ech_grease: Option<bool>,
+    permute_extensions: Option<bool>,
+    grease_enabled: Option<bool>,
+    enable_ocsp_stapling: Option<bool>,
+    enable_signed_cert_timestamps: Option<bool>,
+    record_size_limit: Option<u16>,
+    psk_skip_session_ticket: Option<bool>,
+    key_shares_limit: Option<u8>,
+    psk_dhe_ke: Option<bool>,
+    renegotiation: Option<bool>,
+    delegated_credentials: Option<StringList>,
+    curves: Option<StringList>,
+    ciphers: Option<StringList>,
+    sigalgs: Option<StringList>,
+    certificate_compression: Option<Vec<String>>,
+    extension_permutation: Option<Vec<u16>>,
+    aes_hw_override: Option<bool>,
+    random_aes_hw_override: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct Http2Definition {
+    initial_window_size: Option<u32>,
+    initial_conn_window_size: Option<u32>,
+    initial_max_send_streams: Option<usize>,
+    initial_stream_id: Option<u32>,
+    max_frame_size: Option<u32>,
+    max_header_list_size: Option<u32>,
+    header_table_size: Option<u32>,
+    max_concurrent_streams: Option<u32>,
+    enable_push: Option<bool>,
+    enable_connect_protocol: Option<bool>,
+    no_rfc7540_priorities: Option<bool>,
+    adaptive_window: Option<bool>,
+    pseudo_header_order: Option<Vec<String>>,
+    settings_order: Option<Vec<String>>,
+    headers_stream_dependency: Option<StreamDependencyDefinition>,
+    priorities: Option<Vec<PriorityDefinition>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct StreamDependencyDefinition {
+    dependency_id: u32,
+    weight: u8,
+    #[serde(default)]
+    exclusive: bool,
+}
+
+impl StreamDependencyDefinition {
+    fn build(&self) -> StreamDependency {
+        StreamDependency::new(self.dependency_id.into(), self.weight, self.exclusive)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct PriorityDefinition {
+    stream_id: u32,
+    dependency: StreamDependencyDefinition,
+}
+
+impl ProfileDefinition {
+    fn build(&self, settings: &EmulationSettings) -> Result<wreq::Emulation> {
+        // The OS and skip flags shape the base profile; custom sections are layered on top
+        let mut emulation = match self.extends.as_deref() {
+            Some(base) => resolve_builtin(base)
+                .map(|info| emulation_option(info.emulation, settings).emulation())
+                .ok_or_else(|| anyhow::anyhow!("Unknown base profile '{}' in `extends`", base))?,
+            None => wreq::Emulation::default(),
+        };
+
+        if let Some(tls) = self.tls.clone() {
+            tls.apply(
+                emulation
+                    .tls_options_mut()
+                    .get_or_insert_with(TlsOptions::default),
+            )?;
+        }
+
+        if let Some(http2) = self.http2.clone().filter(|_| !settings.skip_http2) {
+            http2.apply(
+                emulation
+                    .http2_options_mut()
+                    .get_or_insert_with(Http2Options::default),
+            )?;
+        }
+
+        if let Some(headers) = self.headers.as_ref().filter(|_| !settings.skip_headers) {
+            let entries = match headers {
+                HeaderList::Tuples(tuples) => tuples.clone(),
+                HeaderList::Object(map) => map.clone().into_iter().collect(),
+            };
+            apply_headers(emulation.headers_mut(), entries)?;
+        }
+
+        if let Some(order) = &self.header_order {
+            let mut orig_headers = OrigHeaderMap::new();
+            for name in order.iter().cloned() {
+                HeaderName::from_bytes(name.as_bytes())
+                    .with_context(|| format!("Invalid header name in `headerOrder`: {}", name))?;
+                orig_headers.insert(name);
+            }
+            *emulation.orig_headers_mut() = orig_headers;
+        }
+
+        Ok(emulation)
+    }
+}
+
+fn apply_headers(target: &mut HeaderMap, entries: Vec<(String, String)>) -> Result<()> {
+    for (name, value) in entries {
+        let header_name = HeaderName::from_bytes(name.as_bytes())
+            .with_context(|| format!("Invalid header name: {}", name))?;
+        let header_value = HeaderValue::from_str(&value)
+            .with_context(|| format!("Invalid value for header {}", name))?;
+        target.insert(header_name, header_value);
+    }
+    Ok(())
+}
+
+impl TlsDefinition {
+    fn apply(self, tls: &mut TlsOptions) -> Result<()> {
+        if let Some(protocols) = self.alpn_protocols {
+            let parsed = protocols
+                .iter()
+                .map(String::as_str)
+                .map(parse_alpn)
+                .collect::<Result<Vec<_>>>()?;
+            tls.alpn_protocols = Some(Cow::Owned(parsed));
+        }
+        if let Some(protocols) = self.alps_protocols {
+            let parsed = protocols
+                .iter()
+                .map(String::as_str)
+                .map(parse_alps)
+                .collect::<Result<Vec<_>>>()?;
+            tls.alps_protocols = Some(Cow::Owned(parsed));
+        }
+        if let Some(version) = self.min_version {
+            tls.min_tls_version = Some(parse_tls_version(&version)?);
+        }
+        if let Some(version) = self.max_version {
+            tls.max_tls_version = Some(parse_tls_version(&version)?);
+        }
+        if let Some(algorithms) = self.certificate_compression {
+            let parsed = algorithms
+                .iter()
+                .map(String::as_str)
+                .map(parse_cert_compression)
+                .collect::<Result<Vec<_>>>()?;
+            tls.certificate_compression_algorithms = Some(Cow::Owned(parsed));
+        }
+        if let Some(permutation) = self.extension_permutation {
+            let parsed: Vec<ExtensionType> =
+                permutation.into_iter().map(ExtensionType::from).collect();
+            tls.extension_permutation = Some(Cow::Owned(parsed));
+        }
+
+        if let Some(value) = self.alps_use_new_codepoint {
+            tls.alps_use_new_codepoint = value;
+        }
+        if let Some(value) = self.session_ticket {
+            tls.session_ticket = value;
+        }
+        if let Some(value) = self.pre_shared_key {
+            tls.pre_shared_key = value;
+        }
+        if let Some(value) = self.enable_ech_grease {
+            tls.enable_ech_grease = value;
+        }
+        if let Some(value) = self.permute_extensions {
+            tls.permute_extensions = Some(value);
+        }
+        if let Some(value) = self.grease_enabled {
+            tls.grease_enabled = Some(value);
+        }
+        if let Some(value) = self.enable_ocsp_stapling {
+            tls.enable_ocsp_stapling = value;
+        }
+        if let Some(value) = self.enable_signed_cert_timestamps {
+            tls.enable_signed_cert_timestamps = value;
+        }
+        if let Some(value) = self.record_size_limit {
+            tls.record_size_limit = Some(value);
+        }
+        if let Some(value) = self.psk_skip_session_ticket {
+            tls.psk_skip_session_ticket = value;
+        }
+        if let Some(value) = self.key_shares_limit {
+            tls.key_shares_limit = Some(value);
+        }
+        if let Some(value) = self.psk_dhe_ke {
+            tls.psk_dhe_ke = value;
+        }
+        if let Some(value) = self.renegotiation {
+            tls.renegotiation = value;
+        }
+        if let Some(value) = self.delegated_credentials {
+            tls.delegated_credentials = Some(value.joined());
+        }
+        if let Some(value) = self.curves {
+            tls.curves_list = Some(value.joined());
+        }
+        if let Some(value) = self.ciphers {
+            tls.cipher_list = Some(value.joined());
+        }
+        if let Some(value) = self.sigalgs {
+            tls.sigalgs_list = Some(value.joined());
+        }
+        if let Some(value) = self.aes_hw_override {
+            tls.aes_hw_override = Some(value);
+        }
+        if let Some(value) = self.random_aes_hw_override {
+            tls.random_aes_hw_override = value;
+        }
+
+        Ok(())
+    }
+}
+
+impl Http2Definition {
+    fn apply(self, http2: &mut Http2Options) -> Result<()> {
+        if let Some(value) = self.initial_window_size {
+            http2.initial_window_size = value;
+        }
+        if let Some(value) = self.initial_conn_window_size {
+            http2.initial_conn_window_size = value;
+        }
+        if let Some(value) = self.initial_max_send_streams {
+            http2.initial_max_send_streams = value;
+        }
+        if let Some(value) = self.initial_stream_id {
+            http2.initial_stream_id = Some(value);
+        }
+        if let Some(value) = self.max_frame_size {
+            http2.max_frame_size = Some(value);
+        }
+        if let Some(value) = self.max_header_list_size {
+            http2.max_header_list_size = Some(value);
+        }
+        if let Some(value) = self.header_table_size {
+            http2.header_table_size = Some(value);
+        }
+        if let Some(value) = self.max_concurrent_streams {
+            http2.max_concurrent_streams = Some(value);
+        }
+        if let Some(value) = self.enable_push {
+            http2.enable_push = Some(value);
+        }
+        if let Some(value) = self.enable_connect_protocol {
+            http2.enable_connect_protocol = Some(value);
+        }
+        if let Some(value) = self.no_rfc7540_priorities {
+            http2.no_rfc7540_priorities = Some(value);
+        }
+        if let Some(value) = self.adaptive_window {
+            http2.adaptive_window = value;
+        }
+
+        if let Some(order) = self.pseudo_header_order {
+            let ids = order
+                .iter()
+                .map(String::as_str)
+                .map(parse_pseudo_id)
+                .collect::<Result<Vec<_>>>()?;
+            http2.headers_pseudo_order = Some(PseudoOrder::builder().extend(ids).build());
+        }
+
+        if let Some(order) = self.settings_order {
+            let ids = order
+                .iter()
+                .map(String::as_str)
+                .map(parse_setting_id)
+                .collect::<Result<Vec<_>>>()?;
+            http2.settings_order = Some(SettingsOrder::builder().extend(ids).build());
+        }
+
+        if let Some(dependency) = self.headers_stream_dependency {
+            http2.headers_stream_dependency = Some(dependency.build());
+        }
+
+        if let Some(priorities) = self.priorities {
+            let frames = priorities
+                .iter()
+                .map(|p| Priority::new(p.stream_id.into(), p.dependency.build()));
+            http2.priorities = Some(Priorities::builder().extend(frames).build());
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_tls_version(value: &str) -> Result<TlsVersion> {
+    match value
+        .trim()
+        .to_ascii_lowercase()
+        .trim_start_matches("tls")
+        .trim_start_matches(['v', '_', ' '])
+    {
+        "1.0" | "1_0" => Ok(TlsVersion::TLS_1_0),
+        "1.1" | "1_1" => Ok(TlsVersion::TLS_1_1),
+        "1.2" | "1_2" => Ok(TlsVersion::TLS_1_2),
+        "1.3" | "1_3" => Ok(TlsVersion::TLS_1_3),
+        _ => anyhow::bail!("Unsupported TLS version: {}", value),
+    }
+}
+
+fn parse_alpn(value: &str) -> Result<AlpnProtocol> {
+    match value {
+        "http/1.1" => Ok(AlpnProtocol::HTTP1),
+        "h2" => Ok(AlpnProtocol::HTTP2),
+        "h3" => Ok(AlpnProtocol::HTTP3),
+        _ => anyhow::bail!(
+            "Unsupported ALPN protocol: {} (expected http/1.1, h2 or h3)",
+            value
+        ),
+    }
+}
+
+fn parse_alps(value: &str) -> Result<AlpsProtocol> {
+    match value {
+        "http/1.1" => Ok(AlpsProtocol::HTTP1),
+        "h2" => Ok(AlpsProtocol::HTTP2),
+        "h3" => Ok(AlpsProtocol::HTTP3),
+        _ => anyhow::bail!(
+            "Unsupported ALPS protocol: {} (expected http/1.1, h2 or h3)",
+            value
+        ),
+    }
+}
+
+fn parse_cert_compression(value: &str) -> Result<CertificateCompressionAlgorithm> {
+    match value.to_ascii_lowercase().as_str() {
+        "zlib" => Ok(CertificateCompressionAlgorithm::ZLIB),
+        "brotli" => Ok(CertificateCompressionAlgorithm::BROTLI),
+        "zstd" => Ok(CertificateCompressionAlgorithm::ZSTD),
+        _ => anyhow::bail!("Unsupported certificate compression algorithm: {}", value),
+    }
+}
+
+fn parse_pseudo_id(value: &str) -> Result<PseudoId> {
+    match value.trim_start_matches(':').to_ascii_lowercase().as_str() {
+        "method" => Ok(PseudoId::Method),
+        "scheme" => Ok(PseudoId::Scheme),
+        "authority" => Ok(PseudoId::Authority),
+        "path" => Ok(PseudoId::Path),
+        "protocol" => Ok(PseudoId::Protocol),
+        _ => anyhow::bail!("Unsupported pseudo-header: {}", value),
+    }
+}
+
+fn parse_setting_id(value: &str) -> Result<SettingId> {
+    // Accept both `HEADER_TABLE_SIZE` and `headerTableSize` spellings
+    let normalized: String = value
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+
+    match normalized.as_str() {
+        "headertablesize" => Ok(SettingId::HeaderTableSize),
+        "enablepush" => Ok(SettingId::EnablePush),
+        "maxconcurrentstreams" => Ok(SettingId::MaxConcurrentStreams),
+        "initialwindowsize" => Ok(SettingId::InitialWindowSize),
+        "maxframesize" => Ok(SettingId::MaxFrameSize),
+        "maxheaderlistsize" => Ok(SettingId::MaxHeaderListSize),
+        "enableconnectprotocol" => Ok(SettingId::EnableConnectProtocol),
+        "norfc7540priorities" => Ok(SettingId::NoRfc7540Priorities),
+        _ => anyhow::bail!("Unsupported HTTP/2 setting: {}", value),
+    }
+}
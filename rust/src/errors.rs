@@ -0,0 +1,300 @@
+use crate::dns::DnsError;
+use crate::proxy::without_credentials;
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+
+/// Stable code of what made a request fail, thrown to JS as the error's `code`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The request, or waiting to send it, took longer than its timeout
+    Timeout,
+    /// The host name could not be resolved
+    Dns,
+    /// TLS handshake or certificate failure, with the site or with an HTTPS proxy
+    Tls,
+    ConnectionRefused,
+    /// The connection was reset or closed before the response was complete
+    ConnectionReset,
+    /// Any other failure to open a connection
+    Connect,
+    /// The proxy was reached but did not open the CONNECT tunnel
+    ProxyConnect,
+    /// The proxy could not be picked or reached
+    Proxy,
+    /// Too many redirects, or a redirect that could not be followed
+    Redirect,
+    /// The response body could not be read
+    Body,
+    /// The response body could not be decoded
+    Decode,
+    /// The request was malformed: a bad URL, method or header
+    InvalidRequest,
+    Aborted,
+    /// `only-if-cached` and nothing usable was cached
+    NotCached,
+    /// A replaying cassette has nothing recorded for the request
+    Cassette,
+    /// A session hook failed or did not answer
+    Hook,
+    /// Any other protocol failure while the request was in flight
+    Request,
+    /// The session does not exist, was evicted, or was created with other settings
+    Session,
+    /// A session snapshot could not be read or restored
+    Snapshot,
+    Unknown,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Timeout => "ERR_TIMEOUT",
+            Self::Dns => "ERR_DNS",
+            Self::Tls => "ERR_TLS",
+            Self::ConnectionRefused => "ERR_CONNECTION_REFUSED",
+            Self::ConnectionReset => "ERR_CONNECTION_RESET",
+            Self::Connect => "ERR_CONNECT",
+            Self::ProxyConnect => "ERR_PROXY_CONNECT",
+            Self::Proxy => "ERR_PROXY",
+            Self::Redirect => "ERR_REDIRECT",
+            Self::Body => "ERR_BODY",
+            Self::Decode => "ERR_DECODE",
+            Self::InvalidRequest => "ERR_INVALID_REQUEST",
+            Self::Aborted => "ERR_ABORTED",
+            Self::NotCached => "ERR_NOT_CACHED",
+            Self::Cassette => "ERR_CASSETTE",
+            Self::Hook => "ERR_HOOK",
+            Self::Request => "ERR_REQUEST",
+            Self::Session => "ERR_SESSION",
+            Self::Snapshot => "ERR_SNAPSHOT",
+            Self::Unknown => "ERR_UNKNOWN",
+        }
+    }
+
+    // Failures that may well not happen again on a fresh attempt
+    fn retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::Timeout
+                | Self::Dns
+                | Self::ConnectionRefused
+                | Self::ConnectionReset
+                | Self::Connect
+                | Self::Proxy
+                | Self::Body
+        )
+    }
+}
+
+/// Where in its life a request was when it failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Before anything was sent: options, cache, cassette
+    Setup,
+    /// Waiting for rate limits
+    Queue,
+    /// Picking the proxy, or talking to it
+    Proxy,
+    Dns,
+    Connect,
+    Tls,
+    /// Sending the request and waiting for the response head
+    Request,
+    Body,
+    /// Running a session hook
+    Hook,
+}
+
+impl Phase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Setup => "setup",
+            Self::Queue => "queue",
+            Self::Proxy => "proxy",
+            Self::Dns => "dns",
+            Self::Connect => "connect",
+            Self::Tls => "tls",
+            Self::Request => "request",
+            Self::Body => "body",
+            Self::Hook => "hook",
+        }
+    }
+}
+
+/// A failure classified where it happened rather than from what wreq reports.
+/// Displays as its message, so it can be a context without changing the error's text.
+#[derive(Debug)]
+pub struct Failure {
+    code: ErrorCode,
+    phase: Phase,
+    message: String,
+}
+
+impl Failure {
+    pub fn new(code: ErrorCode, phase: Phase, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            phase,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl StdError for Failure {}
+
+/// The request an error belongs to. As a context it reads `METHOD url`.
+#[derive(Debug)]
+pub struct RequestTarget {
+    method: String,
+    url: String,
+    proxy: Option<String>,
+}
+
+impl RequestTarget {
+    pub fn new(method: &str, url: &str, proxy: Option<&str>) -> Self {
+        Self {
+            method: method.to_string(),
+            url: url.to_string(),
+            proxy: proxy.map(str::to_string),
+        }
+    }
+}
+
+impl fmt::Display for RequestTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.url)
+    }
+}
+
+/// What JS is told about a failed request besides the message
+#[derive(Debug)]
+pub struct ErrorInfo {
+    pub code: ErrorCode,
+    pub phase: Phase,
+    pub retryable: bool,
+    /// URL that failed, which is where a redirect led if one was followed
+    pub url: Option<String>,
+    /// Proxy the request went through, without credentials
+    pub proxy: Option<String>,
+}
+
+impl ErrorInfo {
+    pub fn classify(error: &anyhow::Error) -> Self {
+        let wreq_error = error.downcast_ref::<wreq::Error>();
+        let target = error.downcast_ref::<RequestTarget>();
+        let (code, phase) = if let Some(failure) = error.downcast_ref::<Failure>() {
+            (failure.code, failure.phase)
+        } else if let Some(error) = wreq_error {
+            let proxied = target.is_some_and(|target| target.proxy.is_some());
+            classify_wreq(error, proxied)
+        } else {
+            error
+                .chain()
+                .find_map(cause_code)
+                .map_or((ErrorCode::Unknown, Phase::Setup), |code| {
+                    (code, phase_of(code, Phase::Request))
+                })
+        };
+
+        let retryable = code.retryable();
+        let url = wreq_error
+            .and_then(wreq::Error::uri)
+            .map(ToString::to_string)
+            .or_else(|| target.map(|target| target.url.clone()));
+        let proxy = target
+            .and_then(|target| target.proxy.as_deref())
+            .map(without_credentials);
+
+        Self {
+            code,
+            phase,
+            retryable,
+            url,
+            proxy,
+        }
+    }
+}
+
+fn classify_wreq(error: &wreq::Error, proxied: bool) -> (ErrorCode, Phase) {
+    let phase = if error.is_builder() {
+        Phase::Setup
+    } else if error.is_body() || error.is_decode() {
+        Phase::Body
+    } else if error.is_connect() {
+        Phase::Connect
+    } else {
+        Phase::Request
+    };
+
+    let sources = std::iter::successors(error.source(), |error| error.source());
+    let code = if error.is_builder() {
+        ErrorCode::InvalidRequest
+    } else if error.is_timeout() {
+        ErrorCode::Timeout
+    } else if error.is_redirect() {
+        ErrorCode::Redirect
+    } else if error.is_decode() {
+        ErrorCode::Decode
+    } else if error.is_tls() {
+        ErrorCode::Tls
+    } else if let Some(code) = sources.find_map(cause_code) {
+        code
+    } else if error.is_connect() && proxied {
+        // Nothing failed beneath: DNS, the socket and TLS all worked, so it is the proxy that
+        // refused or dropped the CONNECT. wreq reports neither its status nor its headers.
+        ErrorCode::ProxyConnect
+    } else if error.is_connect() {
+        ErrorCode::Connect
+    } else if error.is_body() {
+        ErrorCode::Body
+    } else {
+        ErrorCode::Request
+    };
+
+    (code, phase_of(code, phase))
+}
+
+// Codes that say where the request was regardless of what wreq reports
+fn phase_of(code: ErrorCode, phase: Phase) -> Phase {
+    match code {
+        ErrorCode::Dns => Phase::Dns,
+        ErrorCode::Tls => Phase::Tls,
+        ErrorCode::ProxyConnect => Phase::Proxy,
+        ErrorCode::InvalidRequest => Phase::Setup,
+        _ => phase,
+    }
+}
+
+// wreq keeps what went wrong in its source chain
+fn cause_code(error: &(dyn StdError + 'static)) -> Option<ErrorCode> {
+    if error.is::<DnsError>() {
+        return Some(ErrorCode::Dns);
+    }
+    if let Some(error) = error.downcast_ref::<io::Error>() {
+        return match error.kind() {
+            io::ErrorKind::TimedOut => Some(ErrorCode::Timeout),
+            io::ErrorKind::ConnectionRefused => Some(ErrorCode::ConnectionRefused),
+            io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::UnexpectedEof => Some(ErrorCode::ConnectionReset),
+            _ => None,
+        };
+    }
+    if error.is::<boring2::ssl::Error>() || error.is::<boring2::error::ErrorStack>() {
+        return Some(ErrorCode::Tls);
+    }
+    if error.is::<http::header::InvalidHeaderName>()
+        || error.is::<http::header::InvalidHeaderValue>()
+    {
+        return Some(ErrorCode::InvalidRequest);
+    }
+    None
+}
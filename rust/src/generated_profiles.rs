@@ -1,90 +1,905 @@
 // Auto-generated from build script
 // DO NOT EDIT MANUALLY
 
-pub const BROWSER_PROFILES: &[&str] = &[
-    "chrome_100",
-    "chrome_101",
-    "chrome_104",
-    "chrome_105",
-    "chrome_106",
-    "chrome_107",
-    "chrome_108",
-    "chrome_109",
-    "chrome_110",
-    "chrome_114",
-    "chrome_116",
-    "chrome_117",
-    "chrome_118",
-    "chrome_119",
-    "chrome_120",
-    "chrome_123",
-    "chrome_124",
-    "chrome_126",
-    "chrome_127",
-    "chrome_128",
-    "chrome_129",
-    "chrome_130",
-    "chrome_131",
-    "chrome_132",
-    "chrome_133",
-    "chrome_134",
-    "chrome_135",
-    "chrome_136",
-    "chrome_137",
-    "chrome_138",
-    "chrome_139",
-    "chrome_140",
-    "chrome_141",
-    "chrome_142",
-    "edge_101",
-    "edge_122",
-    "edge_127",
-    "edge_131",
-    "edge_134",
-    "opera_116",
-    "opera_117",
-    "opera_118",
-    "opera_119",
-    "safari_ios_17.2",
-    "safari_ios_17.4.1",
-    "safari_ios_16.5",
-    "safari_15.3",
-    "safari_15.5",
-    "safari_15.6.1",
-    "safari_16",
-    "safari_16.5",
-    "safari_17.0",
-    "safari_17.2.1",
-    "safari_17.4.1",
-    "safari_17.5",
-    "safari_18",
-    "safari_ipad_18",
-    "safari_18.2",
-    "safari_ios_18.1.1",
-    "safari_18.3",
-    "safari_18.3.1",
-    "safari_18.5",
-    "safari_26",
-    "safari_ipad_26",
-    "safari_ios_26",
-    "firefox_109",
-    "firefox_117",
-    "firefox_128",
-    "firefox_133",
-    "firefox_135",
-    "firefox_private_135",
-    "firefox_android_135",
-    "firefox_136",
-    "firefox_private_136",
-    "firefox_139",
-    "firefox_142",
-    "firefox_143",
-    "okhttp_3.9",
-    "okhttp_3.11",
-    "okhttp_3.13",
-    "okhttp_3.14",
-    "okhttp_4.9",
-    "okhttp_4.10",
-    "okhttp_4.12",
-    "okhttp_5",
+use wreq_util::Emulation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Desktop,
+    Mobile,
+    Ipad,
+}
+
+impl Platform {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Platform::Desktop => "desktop",
+            Platform::Mobile => "mobile",
+            Platform::Ipad => "ipad",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    Blink,
+    Gecko,
+    Webkit,
+    Okhttp,
+}
+
+impl Engine {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Engine::Blink => "blink",
+            Engine::Gecko => "gecko",
+            Engine::Webkit => "webkit",
+            Engine::Okhttp => "okhttp",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ProfileInfo {
+    pub name: &'static str,
+    pub emulation: Emulation,
+    pub family: &'static str,
+    pub group: &'static str,
+    pub version: &'static str,
+    pub platform: Platform,
+    pub private: bool,
+    pub engine: Engine,
+}
+
+pub const BROWSER_PROFILES: &[ProfileInfo] = &[
+    ProfileInfo {
+        name: "chrome_100",
+        emulation: Emulation::Chrome100,
+        family: "chrome",
+        group: "chrome",
+        version: "100",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_101",
+        emulation: Emulation::Chrome101,
+        family: "chrome",
+        group: "chrome",
+        version: "101",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_104",
+        emulation: Emulation::Chrome104,
+        family: "chrome",
+        group: "chrome",
+        version: "104",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_105",
+        emulation: Emulation::Chrome105,
+        family: "chrome",
+        group: "chrome",
+        version: "105",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_106",
+        emulation: Emulation::Chrome106,
+        family: "chrome",
+        group: "chrome",
+        version: "106",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_107",
+        emulation: Emulation::Chrome107,
+        family: "chrome",
+        group: "chrome",
+        version: "107",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_108",
+        emulation: Emulation::Chrome108,
+        family: "chrome",
+        group: "chrome",
+        version: "108",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_109",
+        emulation: Emulation::Chrome109,
+        family: "chrome",
+        group: "chrome",
+        version: "109",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_110",
+        emulation: Emulation::Chrome110,
+        family: "chrome",
+        group: "chrome",
+        version: "110",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_114",
+        emulation: Emulation::Chrome114,
+        family: "chrome",
+        group: "chrome",
+        version: "114",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_116",
+        emulation: Emulation::Chrome116,
+        family: "chrome",
+        group: "chrome",
+        version: "116",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_117",
+        emulation: Emulation::Chrome117,
+        family: "chrome",
+        group: "chrome",
+        version: "117",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_118",
+        emulation: Emulation::Chrome118,
+        family: "chrome",
+        group: "chrome",
+        version: "118",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_119",
+        emulation: Emulation::Chrome119,
+        family: "chrome",
+        group: "chrome",
+        version: "119",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_120",
+        emulation: Emulation::Chrome120,
+        family: "chrome",
+        group: "chrome",
+        version: "120",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_123",
+        emulation: Emulation::Chrome123,
+        family: "chrome",
+        group: "chrome",
+        version: "123",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_124",
+        emulation: Emulation::Chrome124,
+        family: "chrome",
+        group: "chrome",
+        version: "124",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_126",
+        emulation: Emulation::Chrome126,
+        family: "chrome",
+        group: "chrome",
+        version: "126",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_127",
+        emulation: Emulation::Chrome127,
+        family: "chrome",
+        group: "chrome",
+        version: "127",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_128",
+        emulation: Emulation::Chrome128,
+        family: "chrome",
+        group: "chrome",
+        version: "128",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_129",
+        emulation: Emulation::Chrome129,
+        family: "chrome",
+        group: "chrome",
+        version: "129",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_130",
+        emulation: Emulation::Chrome130,
+        family: "chrome",
+        group: "chrome",
+        version: "130",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_131",
+        emulation: Emulation::Chrome131,
+        family: "chrome",
+        group: "chrome",
+        version: "131",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_132",
+        emulation: Emulation::Chrome132,
+        family: "chrome",
+        group: "chrome",
+        version: "132",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_133",
+        emulation: Emulation::Chrome133,
+        family: "chrome",
+        group: "chrome",
+        version: "133",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_134",
+        emulation: Emulation::Chrome134,
+        family: "chrome",
+        group: "chrome",
+        version: "134",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_135",
+        emulation: Emulation::Chrome135,
+        family: "chrome",
+        group: "chrome",
+        version: "135",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_136",
+        emulation: Emulation::Chrome136,
+        family: "chrome",
+        group: "chrome",
+        version: "136",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_137",
+        emulation: Emulation::Chrome137,
+        family: "chrome",
+        group: "chrome",
+        version: "137",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_138",
+        emulation: Emulation::Chrome138,
+        family: "chrome",
+        group: "chrome",
+        version: "138",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_139",
+        emulation: Emulation::Chrome139,
+        family: "chrome",
+        group: "chrome",
+        version: "139",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_140",
+        emulation: Emulation::Chrome140,
+        family: "chrome",
+        group: "chrome",
+        version: "140",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_141",
+        emulation: Emulation::Chrome141,
+        family: "chrome",
+        group: "chrome",
+        version: "141",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "chrome_142",
+        emulation: Emulation::Chrome142,
+        family: "chrome",
+        group: "chrome",
+        version: "142",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "edge_101",
+        emulation: Emulation::Edge101,
+        family: "edge",
+        group: "edge",
+        version: "101",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "edge_122",
+        emulation: Emulation::Edge122,
+        family: "edge",
+        group: "edge",
+        version: "122",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "edge_127",
+        emulation: Emulation::Edge127,
+        family: "edge",
+        group: "edge",
+        version: "127",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "edge_131",
+        emulation: Emulation::Edge131,
+        family: "edge",
+        group: "edge",
+        version: "131",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "edge_134",
+        emulation: Emulation::Edge134,
+        family: "edge",
+        group: "edge",
+        version: "134",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "opera_116",
+        emulation: Emulation::Opera116,
+        family: "opera",
+        group: "opera",
+        version: "116",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "opera_117",
+        emulation: Emulation::Opera117,
+        family: "opera",
+        group: "opera",
+        version: "117",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "opera_118",
+        emulation: Emulation::Opera118,
+        family: "opera",
+        group: "opera",
+        version: "118",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "opera_119",
+        emulation: Emulation::Opera119,
+        family: "opera",
+        group: "opera",
+        version: "119",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Blink,
+    },
+    ProfileInfo {
+        name: "safari_ios_17.2",
+        emulation: Emulation::SafariIos17_2,
+        family: "safari",
+        group: "safari_ios",
+        version: "17.2",
+        platform: Platform::Mobile,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_ios_17.4.1",
+        emulation: Emulation::SafariIos17_4_1,
+        family: "safari",
+        group: "safari_ios",
+        version: "17.4.1",
+        platform: Platform::Mobile,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_ios_16.5",
+        emulation: Emulation::SafariIos16_5,
+        family: "safari",
+        group: "safari_ios",
+        version: "16.5",
+        platform: Platform::Mobile,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_15.3",
+        emulation: Emulation::Safari15_3,
+        family: "safari",
+        group: "safari",
+        version: "15.3",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_15.5",
+        emulation: Emulation::Safari15_5,
+        family: "safari",
+        group: "safari",
+        version: "15.5",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_15.6.1",
+        emulation: Emulation::Safari15_6_1,
+        family: "safari",
+        group: "safari",
+        version: "15.6.1",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_16",
+        emulation: Emulation::Safari16,
+        family: "safari",
+        group: "safari",
+        version: "16",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_16.5",
+        emulation: Emulation::Safari16_5,
+        family: "safari",
+        group: "safari",
+        version: "16.5",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_17.0",
+        emulation: Emulation::Safari17_0,
+        family: "safari",
+        group: "safari",
+        version: "17.0",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_17.2.1",
+        emulation: Emulation::Safari17_2_1,
+        family: "safari",
+        group: "safari",
+        version: "17.2.1",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_17.4.1",
+        emulation: Emulation::Safari17_4_1,
+        family: "safari",
+        group: "safari",
+        version: "17.4.1",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_17.5",
+        emulation: Emulation::Safari17_5,
+        family: "safari",
+        group: "safari",
+        version: "17.5",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_18",
+        emulation: Emulation::Safari18,
+        family: "safari",
+        group: "safari",
+        version: "18",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_ipad_18",
+        emulation: Emulation::SafariIPad18,
+        family: "safari",
+        group: "safari_ipad",
+        version: "18",
+        platform: Platform::Ipad,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_18.2",
+        emulation: Emulation::Safari18_2,
+        family: "safari",
+        group: "safari",
+        version: "18.2",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_ios_18.1.1",
+        emulation: Emulation::SafariIos18_1_1,
+        family: "safari",
+        group: "safari_ios",
+        version: "18.1.1",
+        platform: Platform::Mobile,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_18.3",
+        emulation: Emulation::Safari18_3,
+        family: "safari",
+        group: "safari",
+        version: "18.3",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_18.3.1",
+        emulation: Emulation::Safari18_3_1,
+        family: "safari",
+        group: "safari",
+        version: "18.3.1",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_18.5",
+        emulation: Emulation::Safari18_5,
+        family: "safari",
+        group: "safari",
+        version: "18.5",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_26",
+        emulation: Emulation::Safari26,
+        family: "safari",
+        group: "safari",
+        version: "26",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_ipad_26",
+        emulation: Emulation::SafariIPad26,
+        family: "safari",
+        group: "safari_ipad",
+        version: "26",
+        platform: Platform::Ipad,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "safari_ios_26",
+        emulation: Emulation::SafariIos26,
+        family: "safari",
+        group: "safari_ios",
+        version: "26",
+        platform: Platform::Mobile,
+        private: false,
+        engine: Engine::Webkit,
+    },
+    ProfileInfo {
+        name: "firefox_109",
+        emulation: Emulation::Firefox109,
+        family: "firefox",
+        group: "firefox",
+        version: "109",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Gecko,
+    },
+    ProfileInfo {
+        name: "firefox_117",
+        emulation: Emulation::Firefox117,
+        family: "firefox",
+        group: "firefox",
+        version: "117",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Gecko,
+    },
+    ProfileInfo {
+        name: "firefox_128",
+        emulation: Emulation::Firefox128,
+        family: "firefox",
+        group: "firefox",
+        version: "128",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Gecko,
+    },
+    ProfileInfo {
+        name: "firefox_133",
+        emulation: Emulation::Firefox133,
+        family: "firefox",
+        group: "firefox",
+        version: "133",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Gecko,
+    },
+    ProfileInfo {
+        name: "firefox_135",
+        emulation: Emulation::Firefox135,
+        family: "firefox",
+        group: "firefox",
+        version: "135",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Gecko,
+    },
+    ProfileInfo {
+        name: "firefox_private_135",
+        emulation: Emulation::FirefoxPrivate135,
+        family: "firefox",
+        group: "firefox_private",
+        version: "135",
+        platform: Platform::Desktop,
+        private: true,
+        engine: Engine::Gecko,
+    },
+    ProfileInfo {
+        name: "firefox_android_135",
+        emulation: Emulation::FirefoxAndroid135,
+        family: "firefox",
+        group: "firefox_android",
+        version: "135",
+        platform: Platform::Mobile,
+        private: false,
+        engine: Engine::Gecko,
+    },
+    ProfileInfo {
+        name: "firefox_136",
+        emulation: Emulation::Firefox136,
+        family: "firefox",
+        group: "firefox",
+        version: "136",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Gecko,
+    },
+    ProfileInfo {
+        name: "firefox_private_136",
+        emulation: Emulation::FirefoxPrivate136,
+        family: "firefox",
+        group: "firefox_private",
+        version: "136",
+        platform: Platform::Desktop,
+        private: true,
+        engine: Engine::Gecko,
+    },
+    ProfileInfo {
+        name: "firefox_139",
+        emulation: Emulation::Firefox139,
+        family: "firefox",
+        group: "firefox",
+        version: "139",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Gecko,
+    },
+    ProfileInfo {
+        name: "firefox_142",
+        emulation: Emulation::Firefox142,
+        family: "firefox",
+        group: "firefox",
+        version: "142",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Gecko,
+    },
+    ProfileInfo {
+        name: "firefox_143",
+        emulation: Emulation::Firefox143,
+        family: "firefox",
+        group: "firefox",
+        version: "143",
+        platform: Platform::Desktop,
+        private: false,
+        engine: Engine::Gecko,
+    },
+    ProfileInfo {
+        name: "okhttp_3.9",
+        emulation: Emulation::OkHttp3_9,
+        family: "okhttp",
+        group: "okhttp",
+        version: "3.9",
+        platform: Platform::Mobile,
+        private: false,
+        engine: Engine::Okhttp,
+    },
+    ProfileInfo {
+        name: "okhttp_3.11",
+        emulation: Emulation::OkHttp3_11,
+        family: "okhttp",
+        group: "okhttp",
+        version: "3.11",
+        platform: Platform::Mobile,
+        private: false,
+        engine: Engine::Okhttp,
+    },
+    ProfileInfo {
+        name: "okhttp_3.13",
+        emulation: Emulation::OkHttp3_13,
+        family: "okhttp",
+        group: "okhttp",
+        version: "3.13",
+        platform: Platform::Mobile,
+        private: false,
+        engine: Engine::Okhttp,
+    },
+    ProfileInfo {
+        name: "okhttp_3.14",
+        emulation: Emulation::OkHttp3_14,
+        family: "okhttp",
+        group: "okhttp",
+        version: "3.14",
+        platform: Platform::Mobile,
+        private: false,
+        engine: Engine::Okhttp,
+    },
+    ProfileInfo {
+        name: "okhttp_4.9",
+        emulation: Emulation::OkHttp4_9,
+        family: "okhttp",
+        group: "okhttp",
+        version: "4.9",
+        platform: Platform::Mobile,
+        private: false,
+        engine: Engine::Okhttp,
+    },
+    ProfileInfo {
+        name: "okhttp_4.10",
+        emulation: Emulation::OkHttp4_10,
+        family: "okhttp",
+        group: "okhttp",
+        version: "4.10",
+        platform: Platform::Mobile,
+        private: false,
+        engine: Engine::Okhttp,
+    },
+    ProfileInfo {
+        name: "okhttp_4.12",
+        emulation: Emulation::OkHttp4_12,
+        family: "okhttp",
+        group: "okhttp",
+        version: "4.12",
+        platform: Platform::Mobile,
+        private: false,
+        engine: Engine::Okhttp,
+    },
+    ProfileInfo {
+        name: "okhttp_5",
+        emulation: Emulation::OkHttp5,
+        family: "okhttp",
+        group: "okhttp",
+        version: "5",
+        platform: Platform::Mobile,
+        private: false,
+        engine: Engine::Okhttp,
+    },
 ];
@@ -0,0 +1,620 @@
+use crate::client::Response;
+use crate::http_cache::CacheStatus;
+use anyhow::{Context, Result};
+use cookie::time::format_description::well_known::Rfc3339;
+use cookie::time::OffsetDateTime;
+use neon::prelude::Finalize;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use wreq::header::{HeaderMap, HeaderValue};
+use wreq::redirect::History;
+use wreq::{Extension, StatusCode, Version};
+
+// Records every request, through a session or not, while set
+static GLOBAL_RECORDER: Lazy<RwLock<Option<Arc<Recorder>>>> = Lazy::new(|| RwLock::new(None));
+
+const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RecorderOptions {
+    /// Bytes kept of each request and response body; longer ones are cut. `None` keeps
+    /// them whole.
+    pub max_body_size: Option<usize>,
+}
+
+impl Default for RecorderOptions {
+    fn default() -> Self {
+        Self {
+            max_body_size: Some(DEFAULT_MAX_BODY_SIZE),
+        }
+    }
+}
+
+/// Keeps the requests made through it and their responses, exported as a HAR 1.2 log
+#[derive(Debug)]
+pub struct Recorder {
+    options: RecorderOptions,
+    entries: Mutex<Vec<Entry>>,
+}
+
+impl Finalize for Recorder {}
+
+impl Recorder {
+    pub fn new(options: RecorderOptions) -> Self {
+        Self {
+            options,
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Add the entries of a finished request: one per redirect followed, then its outcome
+    pub fn record(&self, exchange: &Exchange, result: &Result<Response>) {
+        let entries = exchange.entries(result, self.options.max_body_size);
+        self.entries.lock().unwrap().extend(entries);
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    pub fn to_har(&self) -> Result<String> {
+        let har = Har {
+            log: Log {
+                version: "1.2",
+                creator: Creator {
+                    name: env!("CARGO_PKG_NAME"),
+                    version: env!("CARGO_PKG_VERSION"),
+                },
+                entries: self.entries.lock().unwrap().clone(),
+            },
+        };
+        serde_json::to_string(&har).context("Failed to serialize HAR log")
+    }
+}
+
+pub fn global_recorder() -> Option<Arc<Recorder>> {
+    GLOBAL_RECORDER.read().unwrap().clone()
+}
+
+pub fn set_global_recorder(recorder: Option<Arc<Recorder>>) {
+    *GLOBAL_RECORDER.write().unwrap() = recorder;
+}
+
+/// What one request sent and got, filled in as it goes
+pub struct Exchange {
+    started_at: OffsetDateTime,
+    started: Instant,
+    method: String,
+    url: String,
+    request_headers: Vec<(String, String)>,
+    request_body: Option<String>,
+    // When the request left the rate limit queue and the proxy resolver
+    sent: Option<Instant>,
+    response: Option<Received>,
+}
+
+// The response head as it arrived, before the body is read
+struct Received {
+    at: Instant,
+    version: Version,
+    headers: Vec<(String, String)>,
+    cookies: Vec<Cookie>,
+    redirects: Vec<History>,
+    server_ip: Option<String>,
+    connection: Option<String>,
+}
+
+impl Exchange {
+    pub fn new(method: &str, url: &str) -> Self {
+        Self {
+            started_at: OffsetDateTime::now_utc(),
+            started: Instant::now(),
+            method: match method {
+                "" => "GET".to_string(),
+                method => method.to_uppercase(),
+            },
+            url: url.to_string(),
+            request_headers: Vec::new(),
+            request_body: None,
+            sent: None,
+            response: None,
+        }
+    }
+
+    /// The headers and body handed to the client. Cookies come from the jar, and are
+    /// added by the client when the request is sent.
+    pub fn request(&mut self, headers: &HeaderMap, cookies: &[HeaderValue], body: Option<&str>) {
+        self.request_headers = header_pairs(headers);
+        for cookie in cookies {
+            if let Ok(cookie) = cookie.to_str() {
+                self.request_headers
+                    .push(("cookie".to_string(), cookie.to_string()));
+            }
+        }
+        self.request_body = body.map(str::to_string);
+    }
+
+    pub fn sending(&mut self) {
+        self.sent = Some(Instant::now());
+    }
+
+    pub fn responded(&mut self, response: &wreq::Response) {
+        let redirects = response
+            .extension::<Vec<History>>()
+            .map(|Extension(history)| history.clone())
+            .unwrap_or_default();
+        self.response = Some(Received {
+            at: Instant::now(),
+            version: response.version(),
+            headers: header_pairs(response.headers()),
+            cookies: response
+                .cookies()
+                .map(|cookie| Cookie::from(&cookie))
+                .collect(),
+            redirects,
+            server_ip: response.remote_addr().map(|addr| addr.ip().to_string()),
+            connection: response.local_addr().map(|addr| addr.port().to_string()),
+        });
+    }
+
+    fn entries(&self, result: &Result<Response>, max_body_size: Option<usize>) -> Vec<Entry> {
+        let started_date_time = self.started_at.format(&Rfc3339).unwrap_or_default();
+        let received = self.response.as_ref();
+        let http_version =
+            received.map_or(String::new(), |received| format!("{:?}", received.version));
+
+        // Redirects are followed inside the client, which keeps their responses but not
+        // their timings or the exact headers sent
+        let mut entries = Vec::new();
+        let mut method = self.method.clone();
+        let mut with_body = true;
+        for hop in received
+            .map(|received| &received.redirects[..])
+            .unwrap_or_default()
+        {
+            let url = hop.previous().to_string();
+            let request =
+                self.request_entry(&method, &url, &http_version, with_body, max_body_size);
+            let status = hop.status();
+            entries.push(Entry {
+                started_date_time: started_date_time.clone(),
+                time: 0.0,
+                request,
+                response: EntryResponse {
+                    status: status.as_u16(),
+                    status_text: status.canonical_reason().unwrap_or_default().to_string(),
+                    http_version: http_version.clone(),
+                    cookies: Vec::new(),
+                    headers: named(header_pairs(hop.headers())),
+                    content: Content::new(hop.headers(), "", max_body_size),
+                    redirect_url: hop.uri().to_string(),
+                    headers_size: -1,
+                    body_size: -1,
+                    error: None,
+                },
+                cache: Cache {},
+                timings: Timings::default(),
+                server_ip_address: None,
+                connection: None,
+                comment: Some("Redirect followed by the client; timed as part of the last entry"),
+            });
+            // As the client does: 303, and 301/302 after a POST, continue as a GET
+            let to_get = status == StatusCode::SEE_OTHER
+                || (method == "POST" && matches!(status.as_u16(), 301 | 302));
+            if to_get {
+                method = "GET".to_string();
+                with_body = false;
+            }
+        }
+
+        let url = match result {
+            Ok(response) => response.url.clone(),
+            Err(_) => self.url.clone(),
+        };
+        let request = self.request_entry(&method, &url, &http_version, with_body, max_body_size);
+        let (response, comment) = match result {
+            Ok(response) => self.response_entry(response, &http_version, max_body_size),
+            Err(error) => (EntryResponse::failed(error), None),
+        };
+        let timings = self.timings();
+        entries.push(Entry {
+            started_date_time,
+            time: timings.total(),
+            request,
+            response,
+            cache: Cache {},
+            timings,
+            server_ip_address: received.and_then(|received| received.server_ip.clone()),
+            connection: received.and_then(|received| received.connection.clone()),
+            comment,
+        });
+        entries
+    }
+
+    fn request_entry(
+        &self,
+        method: &str,
+        url: &str,
+        http_version: &str,
+        with_body: bool,
+        max_body_size: Option<usize>,
+    ) -> Request {
+        let body = self.request_body.as_ref().filter(|_| with_body);
+        let cookies = self
+            .request_headers
+            .iter()
+            .filter(|(name, _)| name == "cookie")
+            .flat_map(|(_, value)| value.split(';'))
+            .filter_map(|pair| pair.trim().split_once('='))
+            .map(|(name, value)| Cookie::pair(name, value))
+            .collect();
+        // Parameters as they appear in the URL, still percent-encoded
+        let query = url.split_once('?').map_or("", |(_, query)| query);
+        let query = query.split_once('#').map_or(query, |(query, _)| query);
+        let query_string = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                NameValue::new(name, value)
+            })
+            .collect();
+        let post_data = body.map(|body| {
+            let (text, truncated) = truncate(body, max_body_size);
+            PostData {
+                mime_type: self.header("content-type").unwrap_or_default().to_string(),
+                text: text.to_string(),
+                comment: truncated.then_some("Truncated"),
+            }
+        });
+
+        Request {
+            method: method.to_string(),
+            url: url.to_string(),
+            http_version: http_version.to_string(),
+            cookies,
+            headers: named(self.request_headers.clone()),
+            query_string,
+            post_data,
+            headers_size: -1,
+            body_size: body.map_or(0, String::len) as i64,
+        }
+    }
+
+    fn response_entry(
+        &self,
+        response: &Response,
+        http_version: &str,
+        max_body_size: Option<usize>,
+    ) -> (EntryResponse, Option<&'static str>) {
+        // A response from the cache is described by what the cache returned, not the 304
+        let received = self
+            .response
+            .as_ref()
+            .filter(|_| response.cache_status != Some(CacheStatus::Revalidated));
+        let headers = match received {
+            Some(received) => received.headers.clone(),
+            None => response
+                .headers
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+        };
+        let header_map = header_map(&headers);
+        let status = StatusCode::from_u16(response.status).ok();
+        let comment = match response.cache_status {
+            Some(CacheStatus::Hit) => Some("Served from the session's HTTP cache"),
+            Some(CacheStatus::Revalidated) => {
+                Some("Served from the session's HTTP cache after a 304 Not Modified")
+            }
+            _ => None,
+        };
+
+        let entry = EntryResponse {
+            status: response.status,
+            status_text: status
+                .and_then(|status| status.canonical_reason())
+                .unwrap_or_default()
+                .to_string(),
+            http_version: http_version.to_string(),
+            cookies: received.map_or_else(Vec::new, |received| received.cookies.clone()),
+            headers: named(headers),
+            content: Content::new(&header_map, &response.body, max_body_size),
+            redirect_url: String::new(),
+            headers_size: -1,
+            body_size: -1,
+            error: None,
+        };
+        (entry, comment)
+    }
+
+    // Blocked covers the rate limit queue and the proxy resolver. DNS, connecting and TLS
+    // happen inside the client and are counted as waiting.
+    fn timings(&self) -> Timings {
+        let now = Instant::now();
+        let sent = self.sent.unwrap_or(now);
+        let responded = self.response.as_ref().map_or(now, |received| received.at);
+        Timings {
+            blocked: millis(sent.saturating_duration_since(self.started)),
+            wait: millis(responded.saturating_duration_since(sent)),
+            receive: millis(now.saturating_duration_since(responded)),
+            ..Timings::default()
+        }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.request_headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Serialize)]
+struct Har {
+    log: Log,
+}
+
+#[derive(Serialize)]
+struct Log {
+    version: &'static str,
+    creator: Creator,
+    entries: Vec<Entry>,
+}
+
+#[derive(Serialize)]
+struct Creator {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    started_date_time: String,
+    time: f64,
+    request: Request,
+    response: EntryResponse,
+    cache: Cache,
+    timings: Timings,
+    #[serde(rename = "serverIPAddress", skip_serializing_if = "Option::is_none")]
+    server_ip_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    connection: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+    method: String,
+    url: String,
+    http_version: String,
+    cookies: Vec<Cookie>,
+    headers: Vec<NameValue>,
+    query_string: Vec<NameValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    post_data: Option<PostData>,
+    headers_size: i64,
+    body_size: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EntryResponse {
+    status: u16,
+    status_text: String,
+    http_version: String,
+    cookies: Vec<Cookie>,
+    headers: Vec<NameValue>,
+    content: Content,
+    #[serde(rename = "redirectURL")]
+    redirect_url: String,
+    headers_size: i64,
+    body_size: i64,
+    // Why the request failed. Custom HAR fields start with an underscore.
+    #[serde(rename = "_error", skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl EntryResponse {
+    // Failed requests are logged with status 0, as browsers do
+    fn failed(error: &anyhow::Error) -> Self {
+        Self {
+            status: 0,
+            status_text: String::new(),
+            http_version: String::new(),
+            cookies: Vec::new(),
+            headers: Vec::new(),
+            content: Content {
+                size: 0,
+                mime_type: String::new(),
+                text: None,
+                comment: None,
+            },
+            redirect_url: String::new(),
+            headers_size: -1,
+            body_size: -1,
+            error: Some(format!("{:#}", error)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Content {
+    size: usize,
+    mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+impl Content {
+    fn new(headers: &HeaderMap, body: &str, max_body_size: Option<usize>) -> Self {
+        let (text, truncated) = truncate(body, max_body_size);
+        Self {
+            size: body.len(),
+            mime_type: headers
+                .get(wreq::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_string(),
+            text: (!body.is_empty()).then(|| text.to_string()),
+            comment: truncated.then(|| format!("Truncated to {} bytes", text.len())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PostData {
+    mime_type: String,
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Cookie {
+    name: String,
+    value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    http_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secure: Option<bool>,
+}
+
+impl Cookie {
+    fn pair(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: value.to_string(),
+            path: None,
+            domain: None,
+            expires: None,
+            http_only: None,
+            secure: None,
+        }
+    }
+}
+
+impl From<&wreq::cookie::Cookie<'_>> for Cookie {
+    fn from(cookie: &wreq::cookie::Cookie<'_>) -> Self {
+        let expires = cookie
+            .expires()
+            .map(OffsetDateTime::from)
+            .and_then(|expires| expires.format(&Rfc3339).ok());
+        Self {
+            path: cookie.path().map(str::to_string),
+            domain: cookie.domain().map(str::to_string),
+            expires,
+            http_only: Some(cookie.http_only()),
+            secure: Some(cookie.secure()),
+            ..Self::pair(cookie.name(), cookie.value())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct NameValue {
+    name: String,
+    value: String,
+}
+
+impl NameValue {
+    fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+// The client keeps no cache state HAR can describe; hits are marked with a comment
+#[derive(Debug, Clone, Serialize)]
+struct Cache {}
+
+/// Milliseconds spent in each phase, -1 for phases that can't be told apart
+#[derive(Debug, Clone, Serialize)]
+struct Timings {
+    blocked: f64,
+    dns: f64,
+    connect: f64,
+    send: f64,
+    wait: f64,
+    receive: f64,
+    ssl: f64,
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Self {
+            blocked: 0.0,
+            dns: -1.0,
+            connect: -1.0,
+            send: 0.0,
+            wait: 0.0,
+            receive: 0.0,
+            ssl: -1.0,
+        }
+    }
+}
+
+impl Timings {
+    fn total(&self) -> f64 {
+        self.blocked + self.send + self.wait + self.receive
+    }
+}
+
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect()
+}
+
+fn header_map(headers: &[(String, String)]) -> HeaderMap {
+    headers
+        .iter()
+        .filter_map(|(name, value)| Some((name.parse().ok()?, value.parse().ok()?)))
+        .collect()
+}
+
+fn named(headers: Vec<(String, String)>) -> Vec<NameValue> {
+    headers
+        .into_iter()
+        .map(|(name, value)| NameValue { name, value })
+        .collect()
+}
+
+// Cut `text` to at most `max` bytes on a character boundary; says whether it was cut
+fn truncate(text: &str, max: Option<usize>) -> (&str, bool) {
+    let Some(max) = max.filter(|max| text.len() > *max) else {
+        return (text, false);
+    };
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    (&text[..end], true)
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
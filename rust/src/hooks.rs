@@ -0,0 +1,124 @@
+use crate::client::{RequestOptions, Response};
+use crate::errors::{ErrorCode, Failure, Phase};
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// The parts of a session request a before-request hook can change
+#[derive(Debug, Clone)]
+pub struct HookRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: IndexMap<String, String>,
+    pub body: Option<String>,
+}
+
+impl HookRequest {
+    pub fn new(options: &RequestOptions) -> Self {
+        Self {
+            method: match options.method.as_str() {
+                "" => "GET".to_string(),
+                method => method.to_uppercase(),
+            },
+            url: options.url.clone(),
+            headers: options.headers.clone(),
+            body: options.body.clone(),
+        }
+    }
+
+    fn apply(self, options: &mut RequestOptions) {
+        options.method = self.method;
+        options.url = self.url;
+        options.headers = self.headers;
+        options.body = self.body;
+    }
+}
+
+/// What a before-request hook decided: send the request, as it may have changed it, or answer
+/// with a response of its own
+#[derive(Debug)]
+pub enum Intercept {
+    Send(HookRequest),
+    Respond(Response),
+}
+
+/// Hands the request to the hook; its decision is sent on the channel once it is made
+pub type BeforeRequestFn = dyn Fn(HookRequest, oneshot::Sender<Result<Intercept>>) + Send + Sync;
+
+/// Hands the request and its response to the hook, which sends back the response to return
+pub type AfterResponseFn =
+    dyn Fn(HookRequest, Response, oneshot::Sender<Result<Response>>) + Send + Sync;
+
+/// JS functions a session runs around each of its requests
+#[derive(Clone, Default)]
+pub struct Hooks {
+    pub before_request: Option<Arc<BeforeRequestFn>>,
+    pub after_response: Option<Arc<AfterResponseFn>>,
+}
+
+impl Hooks {
+    /// Run the before-request hook on `options`, changing them as it says. Returns the hook's
+    /// response when it answers in place of the server.
+    pub async fn before_request(
+        &self,
+        options: &mut RequestOptions,
+        timeout: Duration,
+    ) -> Result<Option<Response>> {
+        let Some(hook) = &self.before_request else {
+            return Ok(None);
+        };
+        let (tx, rx) = oneshot::channel();
+        hook(HookRequest::new(options), tx);
+        match wait(rx, timeout, "beforeRequest").await? {
+            Intercept::Send(request) => {
+                request.apply(options);
+                Ok(None)
+            }
+            Intercept::Respond(response) => Ok(Some(response)),
+        }
+    }
+
+    /// Give the response to the after-response hook, which may replace it
+    pub async fn after_response(
+        &self,
+        request: HookRequest,
+        response: Response,
+        timeout: Duration,
+    ) -> Result<Response> {
+        let Some(hook) = &self.after_response else {
+            return Ok(response);
+        };
+
+        // The hook may change what the server sent, not how it was fetched
+        let proxy = response.proxy.clone();
+        let cache_status = response.cache_status;
+        let (tx, rx) = oneshot::channel();
+        hook(request, response, tx);
+        let mut response = wait(rx, timeout, "afterResponse").await?;
+        response.proxy = proxy;
+        response.cache_status = cache_status;
+        Ok(response)
+    }
+}
+
+// A hook that never answers fails the request after its timeout instead of hanging it
+async fn wait<T>(rx: oneshot::Receiver<Result<T>>, timeout: Duration, name: &str) -> Result<T> {
+    tokio::time::timeout(timeout, rx)
+        .await
+        .map_err(|_| {
+            hook_failure(format!(
+                "{} hook did not answer within {}ms",
+                name,
+                timeout.as_millis()
+            ))
+        })?
+        .with_context(|| hook_failure(format!("{} hook did not answer", name)))?
+        .with_context(|| hook_failure(format!("{} hook failed", name)))
+}
+
+// Whatever goes wrong with a hook is the hook's failure, not the request's
+fn hook_failure(message: String) -> Failure {
+    Failure::new(ErrorCode::Hook, Phase::Hook, message)
+}
@@ -0,0 +1,480 @@
+use anyhow::{Context, Result};
+use cookie::time::format_description::well_known::Rfc2822;
+use cookie::time::OffsetDateTime;
+use indexmap::IndexMap;
+use moka::sync::Cache;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+use wreq::header::{HeaderMap, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH};
+
+// Memory store size when none is given, in bytes
+const DEFAULT_MAX_SIZE: u64 = 50 * 1024 * 1024;
+
+// Heuristic freshness is a tenth of the time since Last-Modified, but no more than a day
+const MAX_HEURISTIC_FRESHNESS: u64 = 24 * 3600;
+
+// Variants kept per URL for responses that Vary; the oldest are dropped first
+const MAX_VARIANTS: usize = 8;
+
+// Statuses that may be cached without explicit freshness (RFC 9110 section 15.1)
+const HEURISTICALLY_CACHEABLE: [u16; 11] = [200, 203, 204, 300, 301, 308, 404, 405, 410, 414, 501];
+
+/// Where a session's HTTP cache keeps responses
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "store", rename_all = "lowercase")]
+pub enum CacheSettings {
+    /// In the process, up to `max_size` bytes of bodies and headers
+    Memory {
+        #[serde(rename = "maxSize", default = "default_max_size")]
+        max_size: u64,
+    },
+    /// One file per URL in the `path` directory, which sessions and processes may share
+    Disk { path: String },
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self::Memory {
+            max_size: DEFAULT_MAX_SIZE,
+        }
+    }
+}
+
+fn default_max_size() -> u64 {
+    DEFAULT_MAX_SIZE
+}
+
+/// How a request uses the cache, as the fetch standard's `RequestCache` does
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CacheMode {
+    /// Use fresh responses, revalidate stale ones, store what may be stored
+    #[default]
+    Default,
+    /// Neither use nor store responses
+    NoStore,
+    /// Skip stored responses but store the new one
+    Reload,
+    /// Revalidate stored responses even when fresh
+    NoCache,
+    /// Use stored responses even when stale
+    ForceCache,
+    /// Use stored responses even when stale, and fail instead of going to the network
+    OnlyIfCached,
+}
+
+impl CacheMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+    }
+}
+
+/// Where a response came from, reported to JS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    /// Served from the cache without contacting the server
+    Hit,
+    /// Fetched from the server
+    Miss,
+    /// Served from the cache after the server confirmed it with a 304
+    Revalidated,
+}
+
+impl CacheStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hit => "hit",
+            Self::Miss => "miss",
+            Self::Revalidated => "revalidated",
+        }
+    }
+}
+
+/// A stored response, as kept in memory and in the disk store's files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedResponse {
+    pub status: u16,
+    /// Response headers, names lowercased, without Set-Cookie
+    pub headers: IndexMap<String, String>,
+    pub body: String,
+    // Values of the request headers named by Vary, `None` for ones the request did not have
+    vary: IndexMap<String, Option<String>>,
+    // Unix seconds when the request was sent and when the response arrived
+    request_time: u64,
+    response_time: u64,
+}
+
+impl CachedResponse {
+    /// A response that just arrived for a request sent at `request_time`
+    pub fn new(
+        status: u16,
+        headers: &IndexMap<String, String>,
+        body: &str,
+        request_time: u64,
+    ) -> Self {
+        // Cookies went to the jar when the response arrived; replaying them would undo changes since
+        let mut headers = headers.clone();
+        headers.shift_remove("set-cookie");
+        Self {
+            status,
+            headers,
+            body: body.to_string(),
+            vary: IndexMap::new(),
+            request_time,
+            response_time: unix_seconds(),
+        }
+    }
+
+    /// Seconds since the server generated the response (RFC 9111 section 4.2.3)
+    pub fn age(&self) -> u64 {
+        let age_value = self.header("age").and_then(|age| age.parse().ok());
+        let apparent_age = self.response_time.saturating_sub(self.date());
+        let response_delay = self.response_time.saturating_sub(self.request_time);
+        let corrected_age = age_value.unwrap_or(0) + response_delay;
+        let resident_time = unix_seconds().saturating_sub(self.response_time);
+        apparent_age.max(corrected_age) + resident_time
+    }
+
+    /// Whether the response may be used for a request with `request_headers` without
+    /// asking the server (RFC 9111 sections 4.2 and 5.2)
+    pub fn is_fresh(&self, request_headers: &HeaderMap) -> bool {
+        let request = directives(request_header(request_headers, "cache-control").as_deref());
+        let pragma = request_header(request_headers, "pragma");
+        if request.contains_key("no-cache")
+            || (request.is_empty() && pragma.is_some_and(|pragma| pragma.contains("no-cache")))
+        {
+            return false;
+        }
+
+        let response = directives(self.header("cache-control"));
+        if response.contains_key("no-cache") {
+            return false;
+        }
+
+        let age = self.age();
+        let lifetime = self.freshness_lifetime();
+        if seconds(&request, "max-age").is_some_and(|max_age| age > max_age) {
+            return false;
+        }
+        if seconds(&request, "min-fresh").is_some_and(|min| lifetime.saturating_sub(age) < min) {
+            return false;
+        }
+        if age < lifetime {
+            return true;
+        }
+
+        // Stale, which the request may accept unless the server forbade it
+        if response.contains_key("must-revalidate") {
+            return false;
+        }
+        match request.get("max-stale") {
+            Some(None) => true,
+            Some(Some(_)) => {
+                seconds(&request, "max-stale").is_some_and(|max| age - lifetime <= max)
+            }
+            None => false,
+        }
+    }
+
+    /// Make the request conditional, so the server can answer 304 if the response is
+    /// still current (RFC 9111 section 4.3.1)
+    pub fn add_validators(&self, request_headers: &mut HeaderMap) {
+        let validators = [
+            (IF_NONE_MATCH, self.header("etag")),
+            (IF_MODIFIED_SINCE, self.header("last-modified")),
+        ];
+        for (name, value) in validators {
+            if let Some(value) = value.and_then(|value| HeaderValue::from_str(value).ok()) {
+                request_headers.insert(name, value);
+            }
+        }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+
+    // The Date header, or when the response arrived if it has none
+    fn date(&self) -> u64 {
+        self.header("date")
+            .and_then(parse_http_date)
+            .unwrap_or(self.response_time)
+    }
+
+    // Seconds the response stays fresh after the server generated it (RFC 9111 section 4.2.1)
+    fn freshness_lifetime(&self) -> u64 {
+        let cache_control = directives(self.header("cache-control"));
+        if cache_control.contains_key("max-age") {
+            return seconds(&cache_control, "max-age").unwrap_or(0);
+        }
+        if let Some(expires) = self.header("expires") {
+            // An invalid date means the response has already expired
+            return parse_http_date(expires)
+                .map_or(0, |expires| expires.saturating_sub(self.date()));
+        }
+        let modified = self.header("last-modified").and_then(parse_http_date);
+        match modified {
+            Some(modified) if HEURISTICALLY_CACHEABLE.contains(&self.status) => {
+                (self.date().saturating_sub(modified) / 10).min(MAX_HEURISTIC_FRESHNESS)
+            }
+            _ => 0,
+        }
+    }
+
+    fn has_validator(&self) -> bool {
+        self.headers.contains_key("etag") || self.headers.contains_key("last-modified")
+    }
+
+    // Whether the response may be stored for a request with `request_headers` (RFC 9111 section 3)
+    fn storable(&self, request_headers: &HeaderMap) -> bool {
+        let request = directives(request_header(request_headers, "cache-control").as_deref());
+        let response = directives(self.header("cache-control"));
+        if request.contains_key("no-store") || response.contains_key("no-store") {
+            return false;
+        }
+        if self.vary_names().iter().any(|name| name == "*") {
+            return false;
+        }
+        // Partial content is not reassembled, and a 304 only ever refreshes a stored response
+        if self.status < 200 || self.status == 206 || self.status == 304 {
+            return false;
+        }
+
+        let explicit = response.contains_key("max-age") || self.headers.contains_key("expires");
+        let allowed = explicit || HEURISTICALLY_CACHEABLE.contains(&self.status);
+        // A response that is stale on arrival and cannot be revalidated would never be used
+        allowed && (self.freshness_lifetime() > 0 || self.has_validator())
+    }
+
+    fn vary_names(&self) -> Vec<String> {
+        self.header("vary")
+            .unwrap_or_default()
+            .split(',')
+            .map(|name| name.trim().to_ascii_lowercase())
+            .filter(|name| !name.is_empty())
+            .collect()
+    }
+
+    fn matches(&self, request_headers: &HeaderMap) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, value)| request_header(request_headers, name) == *value)
+    }
+}
+
+/// The HTTP cache of a session, a private cache in RFC 9111 terms
+pub struct HttpCache {
+    store: Store,
+}
+
+enum Store {
+    // Stored responses per URL
+    Memory(Cache<String, Arc<Vec<CachedResponse>>>),
+    Disk(PathBuf),
+}
+
+// Contents of a disk store file. The URL is kept to tell apart URLs whose names collide.
+#[derive(Serialize, Deserialize)]
+struct DiskEntry {
+    url: String,
+    variants: Vec<CachedResponse>,
+}
+
+impl HttpCache {
+    pub fn new(settings: CacheSettings) -> Result<Self> {
+        let store = match settings {
+            CacheSettings::Memory { max_size } => Store::Memory(
+                Cache::builder()
+                    .max_capacity(max_size)
+                    .weigher(|url: &String, variants: &Arc<Vec<CachedResponse>>| {
+                        let size = url.len() + variants.iter().map(stored_size).sum::<usize>();
+                        size.min(u32::MAX as usize) as u32
+                    })
+                    .build(),
+            ),
+            CacheSettings::Disk { path } => {
+                fs::create_dir_all(&path)
+                    .with_context(|| format!("Failed to create cache directory '{}'", path))?;
+                Store::Disk(PathBuf::from(path))
+            }
+        };
+        Ok(Self { store })
+    }
+
+    /// The most recent stored response for a GET of `url` that matches `request_headers`
+    pub fn lookup(&self, url: &str, request_headers: &HeaderMap) -> Option<CachedResponse> {
+        self.load(url)
+            .into_iter()
+            .filter(|variant| variant.matches(request_headers))
+            .max_by_key(|variant| variant.response_time)
+    }
+
+    /// Store `response` to a GET of `url` with `request_headers`, if it may be stored
+    pub fn store(&self, url: &str, request_headers: &HeaderMap, mut response: CachedResponse) {
+        if !response.storable(request_headers) {
+            return;
+        }
+        response.vary = response
+            .vary_names()
+            .into_iter()
+            .map(|name| {
+                let value = request_header(request_headers, &name);
+                (name, value)
+            })
+            .collect();
+
+        let mut variants = self.load(url);
+        variants.retain(|variant| variant.vary != response.vary);
+        variants.push(response);
+        if variants.len() > MAX_VARIANTS {
+            variants.sort_by_key(|variant| variant.response_time);
+            variants.drain(..variants.len() - MAX_VARIANTS);
+        }
+        self.save(url, variants);
+    }
+
+    /// Update `stored` with the headers of the 304 that confirmed it and store it again
+    /// (RFC 9111 section 4.3.4)
+    pub fn refresh(
+        &self,
+        url: &str,
+        stored: CachedResponse,
+        headers: &IndexMap<String, String>,
+        request_time: u64,
+    ) -> CachedResponse {
+        let mut refreshed = stored;
+        for (name, value) in headers {
+            // These describe the empty 304, not the stored body
+            if matches!(
+                name.as_str(),
+                "content-length" | "content-encoding" | "set-cookie"
+            ) {
+                continue;
+            }
+            refreshed.headers.insert(name.clone(), value.clone());
+        }
+        refreshed.request_time = request_time;
+        refreshed.response_time = unix_seconds();
+
+        let mut variants = self.load(url);
+        variants.retain(|variant| variant.vary != refreshed.vary);
+        variants.push(refreshed.clone());
+        self.save(url, variants);
+        refreshed
+    }
+
+    /// Forget the responses stored for `url`, after an unsafe request to it succeeded
+    /// (RFC 9111 section 4.4)
+    pub fn invalidate(&self, url: &str) {
+        self.save(url, Vec::new());
+    }
+
+    // The disk store is best effort: unreadable or unwritable files count as not cached
+    fn load(&self, url: &str) -> Vec<CachedResponse> {
+        match &self.store {
+            Store::Memory(cache) => cache
+                .get(url)
+                .map(|variants| variants.as_ref().clone())
+                .unwrap_or_default(),
+            Store::Disk(dir) => fs::read(dir.join(file_name(url)))
+                .ok()
+                .and_then(|data| serde_json::from_slice::<DiskEntry>(&data).ok())
+                .filter(|entry| entry.url == url)
+                .map(|entry| entry.variants)
+                .unwrap_or_default(),
+        }
+    }
+
+    fn save(&self, url: &str, variants: Vec<CachedResponse>) {
+        match &self.store {
+            Store::Memory(cache) if variants.is_empty() => cache.invalidate(url),
+            Store::Memory(cache) => cache.insert(url.to_string(), Arc::new(variants)),
+            Store::Disk(dir) => {
+                let path = dir.join(file_name(url));
+                if variants.is_empty() {
+                    let _ = fs::remove_file(path);
+                    return;
+                }
+                let entry = DiskEntry {
+                    url: url.to_string(),
+                    variants,
+                };
+                let Ok(data) = serde_json::to_vec(&entry) else {
+                    return;
+                };
+                // Written aside and renamed, so readers never see half a file
+                let temp = dir.join(format!(".{}.tmp", Uuid::new_v4()));
+                if fs::write(&temp, data).is_err() || fs::rename(&temp, &path).is_err() {
+                    let _ = fs::remove_file(temp);
+                }
+            }
+        }
+    }
+}
+
+fn stored_size(response: &CachedResponse) -> usize {
+    let headers: usize = response
+        .headers
+        .iter()
+        .map(|(name, value)| name.len() + value.len())
+        .sum();
+    response.body.len() + headers
+}
+
+// FNV-1a, which unlike std's hasher is stable across Rust releases, so files stay valid
+fn file_name(url: &str) -> String {
+    let hash = url.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}.json", hash)
+}
+
+// Cache-Control directives with lowercased names and unquoted values
+fn directives(value: Option<&str>) -> HashMap<String, Option<String>> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .map(|directive| match directive.split_once('=') {
+            Some((name, value)) => (
+                name.trim().to_ascii_lowercase(),
+                Some(value.trim().trim_matches('"').to_string()),
+            ),
+            None => (directive.to_ascii_lowercase(), None),
+        })
+        .collect()
+}
+
+fn seconds(directives: &HashMap<String, Option<String>>, name: &str) -> Option<u64> {
+    directives.get(name)?.as_deref()?.parse().ok()
+}
+
+// All values of a request header, joined as if sent on one line
+fn request_header(headers: &HeaderMap, name: &str) -> Option<String> {
+    let values: Vec<&str> = headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .map(str::trim)
+        .collect();
+    (!values.is_empty()).then(|| values.join(", "))
+}
+
+fn parse_http_date(value: &str) -> Option<u64> {
+    let date = OffsetDateTime::parse(value.trim(), &Rfc2822).ok()?;
+    Some(date.unix_timestamp().max(0) as u64)
+}
+
+pub fn unix_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
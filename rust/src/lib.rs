@@ -1,34 +1,174 @@
+mod cassette;
 mod client;
+mod connection_pool;
+mod cookies;
+mod dns;
+mod emulation;
+mod errors;
 mod generated_profiles;
+mod har;
+mod hooks;
+mod http_cache;
+mod logging;
+mod proxy;
+mod proxy_pool;
+mod proxy_resolver;
+mod rate_limit;
+mod selector;
+mod snapshot;
 mod websocket;
 
+use cassette::{set_global_cassette, Cassette, CassetteMode, MatchRules};
 use client::{
-    clear_managed_session, create_managed_session, drop_managed_session, generate_session_id, make_request,
-    RequestOptions, Response, HTTP_RUNTIME,
+    cancel_request, configure_session_limits, create_managed_session, drop_managed_session,
+    generate_session_id, list_managed_sessions, make_request, managed_session_info,
+    observed_managed_connections, reconfigure_managed_session, reset_managed_session,
+    restore_managed_session, session_limits, session_not_found, set_eviction_handler,
+    snapshot_managed_session, Cancellation, RequestOptions, ResetMode, Response, SessionConfig,
+    SessionEviction, SessionExtras, SessionInfo, SessionUpdate, HTTP_RUNTIME,
 };
+use connection_pool::PoolSettings;
+use emulation::{
+    available_profiles, default_profile, parse_os, register_profile, resolve_profile,
+    EmulationProfile, EmulationSettings, ProfileError,
+};
+use errors::ErrorInfo;
 use futures_util::StreamExt;
+use har::{set_global_recorder, Recorder, RecorderOptions};
+use hooks::{AfterResponseFn, BeforeRequestFn, HookRequest, Hooks, Intercept};
+use http_cache::{CacheMode, CacheSettings};
 use indexmap::IndexMap;
+use logging::{configure_logging, log_settings, parse_level, LogRecord, LogSink};
 use neon::prelude::*;
 use neon::types::{
-    buffer::TypedArray, JsArray, JsBoolean, JsNull, JsObject, JsString, JsUndefined, JsValue,
+    buffer::TypedArray, JsArray, JsBoolean, JsBox, JsNull, JsObject, JsString, JsUndefined, JsValue,
 };
-use std::sync::Arc;
-use tokio::sync::{mpsc, Semaphore};
+use proxy::{ProxyMode, ProxySettings};
+use proxy_pool::{ProxyPool, ProxyPoolConfig, RotationStrategy};
+use proxy_resolver::{ProxyResolver, Resolution};
+use rate_limit::{configure_host_limits, RateLimits, MIN_RATE};
+use selector::ProfileSelector;
+use snapshot::{SessionSnapshot, SnapshotDefaults};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, Semaphore};
 use websocket::{
     connect_websocket, get_connection, remove_connection, store_connection, WebSocketOptions,
     WS_RUNTIME,
 };
 use wreq::ws::message::Message;
-use wreq_util::Emulation;
+use wreq_util::EmulationOS;
 
 const WS_EVENT_BUFFER: usize = 64;
 
-// Parse browser string to Emulation enum using serde
-fn parse_emulation(browser: &str) -> Emulation {
-    // Use serde to deserialize the string into the enum
-    // If deserialization fails, default to Chrome142
-    serde_json::from_value(serde_json::Value::String(browser.to_string()))
-        .unwrap_or(Emulation::Chrome142)
+// Resolve a browser name (or the default profile when absent) and apply the emulation settings
+fn parse_emulation(
+    cx: &mut FunctionContext,
+    browser: Option<String>,
+    settings: EmulationSettings,
+) -> NeonResult<EmulationProfile> {
+    let profile = match browser {
+        Some(name) => match resolve_profile(&name) {
+            Ok(profile) => profile,
+            Err(e) => return throw_profile_error(cx, e),
+        },
+        None => EmulationProfile::builtin(default_profile()),
+    };
+
+    Ok(profile.with_settings(settings))
+}
+
+// Throw a TypeError carrying `code`, `profile` and `suggestions` for an unknown profile
+fn throw_profile_error<T>(cx: &mut FunctionContext, error: ProfileError) -> NeonResult<T> {
+    let js_error = cx.type_error(error.to_string())?;
+
+    match error {
+        ProfileError::Unknown { name, suggestions } => {
+            let code = cx.string("ERR_UNKNOWN_PROFILE");
+            js_error.set(cx, "code", code)?;
+            let profile = cx.string(name);
+            js_error.set(cx, "profile", profile)?;
+
+            let js_suggestions = cx.empty_array();
+            for (i, suggestion) in suggestions.iter().enumerate() {
+                let value = cx.string(suggestion);
+                js_suggestions.set(cx, i as u32, value)?;
+            }
+            js_error.set(cx, "suggestions", js_suggestions)?;
+        }
+    }
+
+    cx.throw(js_error)
+}
+
+// Throw an Error carrying the `code`, `phase`, `retryable`, `url` and `proxy` of a failure.
+// `url` is the one requested unless the failure names another; session calls have none.
+fn throw_request_error<'a, C: Context<'a>, T>(
+    cx: &mut C,
+    error: anyhow::Error,
+    url: Option<String>,
+) -> NeonResult<T> {
+    let info = ErrorInfo::classify(&error);
+    let js_error = cx.error(format!("{:#}", error))?;
+
+    let code = cx.string(info.code.as_str());
+    js_error.set(cx, "code", code)?;
+    let phase = cx.string(info.phase.as_str());
+    js_error.set(cx, "phase", phase)?;
+    let retryable = cx.boolean(info.retryable);
+    js_error.set(cx, "retryable", retryable)?;
+    if let Some(url) = info.url.or(url) {
+        let url = cx.string(url);
+        js_error.set(cx, "url", url)?;
+    }
+    if let Some(proxy) = info.proxy {
+        let proxy = cx.string(proxy);
+        js_error.set(cx, "proxy", proxy)?;
+    }
+
+    cx.throw(js_error)
+}
+
+// Read the os/skipHttp2/skipHeaders emulation settings from an options object
+fn parse_emulation_settings(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+) -> NeonResult<EmulationSettings> {
+    let os_name = obj
+        .get_opt(cx, "os")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(cx).ok())
+        .map(|v| v.value(cx));
+
+    let os = match os_name {
+        Some(name) => match parse_os(&name) {
+            Some(os) => os,
+            None => {
+                return cx.throw_type_error(format!(
+                    "Unsupported os '{}'. Expected one of: windows, macos, linux, android, ios",
+                    name
+                ))
+            }
+        },
+        None => EmulationOS::default(),
+    };
+
+    let skip_http2 = obj
+        .get_opt(cx, "skipHttp2")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsBoolean, _>(cx).ok())
+        .map(|v| v.value(cx))
+        .unwrap_or(false);
+
+    let skip_headers = obj
+        .get_opt(cx, "skipHeaders")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsBoolean, _>(cx).ok())
+        .map(|v| v.value(cx))
+        .unwrap_or(false);
+
+    Ok(EmulationSettings {
+        os,
+        skip_http2,
+        skip_headers,
+    })
 }
 
 fn coerce_header_value(cx: &mut FunctionContext, value: Handle<JsValue>) -> NeonResult<String> {
@@ -53,267 +193,1649 @@ fn parse_header_tuple(cx: &mut FunctionContext, tuple: Handle<JsArray>) -> NeonR
     Ok((name, value))
 }
 
-fn parse_headers_from_array(cx: &mut FunctionContext, array: Handle<JsArray>) -> NeonResult<IndexMap<String, String>> {
-    let mut headers = IndexMap::new();
-    let len = array.len(cx);
+fn parse_headers_from_array(cx: &mut FunctionContext, array: Handle<JsArray>) -> NeonResult<IndexMap<String, String>> {
+    let mut headers = IndexMap::new();
+    let len = array.len(cx);
+
+    for i in 0..len {
+        let element: Handle<JsValue> = array.get(cx, i)?;
+        let tuple = element.downcast::<JsArray, _>(cx).or_throw(cx)?;
+        let (name, value) = parse_header_tuple(cx, tuple)?;
+        headers.insert(name, value);
+    }
+
+    Ok(headers)
+}
+
+fn parse_headers_from_object(cx: &mut FunctionContext, obj: Handle<JsObject>) -> NeonResult<IndexMap<String, String>> {
+    let mut headers = IndexMap::new();
+    let keys = obj.get_own_property_names(cx)?;
+    let keys_vec = keys.to_vec(cx)?;
+
+    for key_val in keys_vec {
+        if let Ok(key_str) = key_val.downcast::<JsString, _>(cx) {
+            let key = key_str.value(cx);
+            let value = obj.get(cx, key.as_str())?;
+            let value = coerce_header_value(cx, value)?;
+            headers.insert(key, value);
+        }
+    }
+
+    Ok(headers)
+}
+
+fn parse_headers_from_value(cx: &mut FunctionContext, value: Handle<JsValue>) -> NeonResult<IndexMap<String, String>> {
+    if value.is_a::<JsUndefined, _>(cx) || value.is_a::<JsNull, _>(cx) {
+        return Ok(IndexMap::new());
+    }
+
+    if value.is_a::<JsArray, _>(cx) {
+        let array = value.downcast::<JsArray, _>(cx).or_throw(cx)?;
+        return parse_headers_from_array(cx, array);
+    }
+
+    if value.is_a::<JsObject, _>(cx) {
+        let obj = value.downcast::<JsObject, _>(cx).or_throw(cx)?;
+        return parse_headers_from_object(cx, obj);
+    }
+
+    cx.throw_type_error("headers must be an array or object")
+}
+
+// Convert JS object to RequestOptions
+fn js_object_to_request_options(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+) -> NeonResult<RequestOptions> {
+    // Get URL (required)
+    let url: Handle<JsString> = obj.get(cx, "url")?;
+    let url = url.value(cx);
+
+    // Get browser (optional, defaults to the latest Chrome)
+    let browser = obj
+        .get_opt(cx, "browser")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(cx).ok())
+        .map(|v| v.value(cx));
+
+    let settings = parse_emulation_settings(cx, obj)?;
+    let emulation = parse_emulation(cx, browser, settings)?;
+
+    // Get method (optional, defaults to GET)
+    let method = obj
+        .get_opt(cx, "method")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(cx).ok())
+        .map(|v| v.value(cx))
+        .unwrap_or_else(|| "GET".to_string());
+
+    // Get headers (optional)
+    let headers = if let Ok(Some(headers_val)) = obj.get_opt(cx, "headers") {
+        parse_headers_from_value(cx, headers_val)?
+    } else {
+        IndexMap::new()
+    };
+
+    // Get body (optional)
+    let body = obj
+        .get_opt(cx, "body")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(cx).ok())
+        .map(|v| v.value(cx));
+
+    // Get proxy (optional)
+    let proxy = match obj.get_opt::<JsValue, _, _>(cx, "proxy")? {
+        Some(value) => parse_proxy(cx, value)?,
+        None => None,
+    };
+
+    // Get proxy pool (optional, takes the place of proxy)
+    let proxy_pool = parse_proxy_pool(cx, obj)?;
+
+    // Get timeout (optional, defaults to 30000ms)
+    let timeout = obj
+        .get_opt(cx, "timeout")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsNumber, _>(cx).ok())
+        .map(|v| v.value(cx) as u64)
+        .unwrap_or(30000);
+
+    // Get sessionId (optional; without one the request is stateless)
+    let session_id = obj
+        .get_opt(cx, "sessionId")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(cx).ok())
+        .map(|v| v.value(cx))
+        .filter(|v| !v.trim().is_empty());
+
+    let disable_default_headers = obj
+        .get_opt(cx, "disableDefaultHeaders")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsBoolean, _>(cx).ok())
+        .map(|v| v.value(cx))
+        .unwrap_or(false);
+
+    // Get cache mode (optional; only sessions with a cache use it)
+    let cache_mode = obj
+        .get_opt(cx, "cache")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(cx).ok())
+        .map(|v| v.value(cx));
+    let cache_mode = match cache_mode {
+        Some(mode) => match CacheMode::parse(&mode) {
+            Some(mode) => mode,
+            None => return cx.throw_type_error(format!("Unknown cache mode '{}'", mode)),
+        },
+        None => CacheMode::Default,
+    };
+
+    Ok(RequestOptions {
+        url,
+        emulation,
+        headers,
+        method,
+        body,
+        proxy,
+        proxy_pool,
+        timeout,
+        session_id,
+        disable_default_headers,
+        cache_mode,
+    })
+}
+
+// Convert Response to JS object
+fn response_to_js_object<'a, C: Context<'a>>(
+    cx: &mut C,
+    response: Response,
+) -> JsResult<'a, JsObject> {
+    let obj = cx.empty_object();
+
+    // Status
+    let status = cx.number(response.status as f64);
+    obj.set(cx, "status", status)?;
+
+    // URL
+    let url = cx.string(&response.url);
+    obj.set(cx, "url", url)?;
+
+    // Headers
+    let headers_obj = cx.empty_object();
+    for (key, value) in response.headers {
+        let value_str = cx.string(&value);
+        headers_obj.set(cx, key.as_str(), value_str)?;
+    }
+    obj.set(cx, "headers", headers_obj)?;
+
+    // Cookies
+    let cookies_obj = cx.empty_object();
+    for (key, value) in response.cookies {
+        let value_str = cx.string(&value);
+        cookies_obj.set(cx, key.as_str(), value_str)?;
+    }
+    obj.set(cx, "cookies", cookies_obj)?;
+
+    // Body
+    let body = cx.string(&response.body);
+    obj.set(cx, "body", body)?;
+
+    // Proxy the request went through
+    let proxy: Handle<JsValue> = match response.proxy {
+        Some(proxy) => cx.string(proxy).upcast(),
+        None => cx.null().upcast(),
+    };
+    obj.set(cx, "proxy", proxy)?;
+
+    // Whether the session's cache answered
+    if let Some(cache_status) = response.cache_status {
+        let cache_status = cx.string(cache_status.as_str());
+        obj.set(cx, "cacheStatus", cache_status)?;
+    }
+
+    Ok(obj)
+}
+
+// Main request function exported to Node.js
+fn request(mut cx: FunctionContext) -> JsResult<JsPromise> {
+    // Get the options object
+    let options_obj = cx.argument::<JsObject>(0)?;
+
+    // Convert JS object to Rust struct
+    let options = js_object_to_request_options(&mut cx, options_obj)?;
+    let url = options.url.clone();
+
+    // Given when JS may abort the request with `cancelRequest`
+    let cancellation = options_obj
+        .get_opt(&mut cx, "requestId")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(&mut cx).ok())
+        .map(|v| Cancellation::register(v.value(&mut cx)));
+
+    // Create a promise
+    let (deferred, promise) = cx.promise();
+    let settle_channel = cx.channel();
+
+    HTTP_RUNTIME.spawn(async move {
+        let result = match cancellation {
+            Some(cancellation) => cancellation.run(make_request(options)).await,
+            None => make_request(options).await,
+        };
+
+        // Send result back to JS
+        deferred.settle_with(&settle_channel, move |mut cx| match result {
+            Ok(response) => response_to_js_object(&mut cx, response),
+            Err(e) => throw_request_error(&mut cx, e, Some(url)),
+        });
+    });
+
+    Ok(promise)
+}
+
+// Stop a request started with this `requestId`, releasing its place in rate limit queues
+fn cancel_native_request(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+    let request_id = cx.argument::<JsString>(0)?.value(&mut cx);
+    cancel_request(&request_id);
+    Ok(cx.undefined())
+}
+
+// Get list of available browser profiles with their metadata
+fn get_profiles(mut cx: FunctionContext) -> JsResult<JsArray> {
+    let js_array = cx.empty_array();
+
+    for (i, profile) in available_profiles().into_iter().enumerate() {
+        let obj = cx.empty_object();
+        let name = cx.string(profile.name);
+        obj.set(&mut cx, "name", name)?;
+        let family = cx.string(profile.family);
+        obj.set(&mut cx, "family", family)?;
+        let version: Handle<JsValue> = match profile.version {
+            Some(version) => cx.string(version).upcast(),
+            None => cx.null().upcast(),
+        };
+        obj.set(&mut cx, "version", version)?;
+        let platform: Handle<JsValue> = match profile.platform {
+            Some(platform) => cx.string(platform.as_str()).upcast(),
+            None => cx.null().upcast(),
+        };
+        obj.set(&mut cx, "platform", platform)?;
+        let private = cx.boolean(profile.private);
+        obj.set(&mut cx, "private", private)?;
+        let engine: Handle<JsValue> = match profile.engine {
+            Some(engine) => cx.string(engine.as_str()).upcast(),
+            None => cx.null().upcast(),
+        };
+        obj.set(&mut cx, "engine", engine)?;
+        js_array.set(&mut cx, i as u32, obj)?;
+    }
+
+    Ok(js_array)
+}
+
+// Register a custom emulation profile from a JSON definition
+fn register_custom_profile(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+    let name = cx.argument::<JsString>(0)?.value(&mut cx);
+    let definition = cx.argument::<JsString>(1)?.value(&mut cx);
+
+    if let Err(e) = register_profile(&name, &definition) {
+        let msg = format!("{:#}", e);
+        return cx.throw_error(msg);
+    }
+
+    Ok(cx.undefined())
+}
+
+// Create a weighted profile selector from { weights, seed }
+fn create_profile_selector(mut cx: FunctionContext) -> JsResult<JsBox<ProfileSelector>> {
+    let options = cx.argument::<JsObject>(0)?;
+
+    let weights_obj: Handle<JsObject> = options.get(&mut cx, "weights")?;
+    let keys = weights_obj
+        .get_own_property_names(&mut cx)?
+        .to_vec(&mut cx)?;
+    let mut weights = IndexMap::new();
+
+    for key_val in keys {
+        if let Ok(key_str) = key_val.downcast::<JsString, _>(&mut cx) {
+            let key = key_str.value(&mut cx);
+            let weight: Handle<JsNumber> = weights_obj.get(&mut cx, key.as_str())?;
+            let weight = weight.value(&mut cx);
+            weights.insert(key, weight);
+        }
+    }
+
+    let seed = options
+        .get_opt(&mut cx, "seed")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsNumber, _>(&mut cx).ok())
+        .map(|v| v.value(&mut cx) as u64);
+
+    match ProfileSelector::new(&available_profiles(), &weights, seed) {
+        Ok(selector) => Ok(cx.boxed(selector)),
+        Err(e) => {
+            let msg = format!("{:#}", e);
+            cx.throw_error(msg)
+        }
+    }
+}
+
+// Resolve a profile name or `_latest` alias to the concrete profile name;
+// without an argument, returns the default profile
+fn resolve_profile_name(mut cx: FunctionContext) -> JsResult<JsString> {
+    let browser = cx
+        .argument_opt(0)
+        .and_then(|v| v.downcast::<JsString, _>(&mut cx).ok())
+        .map(|v| v.value(&mut cx));
+
+    let profile = parse_emulation(&mut cx, browser, EmulationSettings::default())?;
+    Ok(cx.string(profile.name()))
+}
+
+// Read a proxy given as a URL string or as { url, username, password, headers }; null means none
+fn parse_proxy(
+    cx: &mut FunctionContext,
+    value: Handle<JsValue>,
+) -> NeonResult<Option<ProxySettings>> {
+    if value.is_a::<JsUndefined, _>(cx) || value.is_a::<JsNull, _>(cx) {
+        return Ok(None);
+    }
+
+    if let Ok(url) = value.downcast::<JsString, _>(cx) {
+        return Ok(Some(ProxySettings::new(url.value(cx))));
+    }
+
+    let obj = value.downcast_or_throw::<JsObject, _>(cx)?;
+    let mut proxy = ProxySettings {
+        url: optional_string(cx, obj, "url")?,
+        http: optional_string(cx, obj, "http")?,
+        https: optional_string(cx, obj, "https")?,
+        ws: optional_string(cx, obj, "ws")?,
+        username: optional_string(cx, obj, "username")?,
+        password: optional_string(cx, obj, "password")?,
+        ..ProxySettings::default()
+    };
+    if let Some(mode) = optional_string(cx, obj, "mode")? {
+        proxy.mode = match ProxyMode::parse(&mode) {
+            Some(mode) => mode,
+            None => return cx.throw_type_error(format!("Unknown proxy mode '{}'", mode)),
+        };
+    }
+    if let Some(no_proxy) = obj
+        .get_opt(cx, "noProxy")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsArray, _>(cx).ok())
+    {
+        for value in no_proxy.to_vec(cx)? {
+            let entry = value.downcast_or_throw::<JsString, _>(cx)?;
+            proxy.no_proxy.push(entry.value(cx));
+        }
+    }
+    if let Some(headers) = obj.get_opt::<JsValue, _, _>(cx, "headers")? {
+        proxy.headers = parse_headers_from_value(cx, headers)?;
+    }
+
+    // The system settings bring their own proxies and NO_PROXY list
+    let has_rules = proxy.url.is_some()
+        || proxy.http.is_some()
+        || proxy.https.is_some()
+        || proxy.ws.is_some()
+        || !proxy.no_proxy.is_empty();
+    if proxy.mode == ProxyMode::System && has_rules {
+        return cx.throw_type_error("System proxy mode takes no proxy URLs or noProxy entries");
+    }
+
+    Ok(Some(proxy))
+}
+
+fn optional_string(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+    key: &str,
+) -> NeonResult<Option<String>> {
+    let value = obj
+        .get_opt(cx, key)?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(cx).ok())
+        .map(|v| v.value(cx));
+    Ok(value)
+}
+
+// Inverse of `parse_proxy`: a plain URL string unless more than a single URL is set
+fn proxy_to_js<'a>(cx: &mut FunctionContext<'a>, proxy: &ProxySettings) -> JsResult<'a, JsValue> {
+    let single_url = proxy
+        .url
+        .as_ref()
+        .filter(|url| *proxy == ProxySettings::new(url.as_str()));
+    if let Some(url) = single_url {
+        return Ok(cx.string(url).upcast());
+    }
+
+    let obj = cx.empty_object();
+    if proxy.mode != ProxyMode::Explicit {
+        let mode = cx.string("system");
+        obj.set(cx, "mode", mode)?;
+    }
+    for (key, value) in [
+        ("url", &proxy.url),
+        ("http", &proxy.http),
+        ("https", &proxy.https),
+        ("ws", &proxy.ws),
+        ("username", &proxy.username),
+        ("password", &proxy.password),
+    ] {
+        if let Some(value) = value {
+            let value = cx.string(value);
+            obj.set(cx, key, value)?;
+        }
+    }
+    if !proxy.no_proxy.is_empty() {
+        let no_proxy = cx.empty_array();
+        for (i, entry) in proxy.no_proxy.iter().enumerate() {
+            let entry = cx.string(entry);
+            no_proxy.set(cx, i as u32, entry)?;
+        }
+        obj.set(cx, "noProxy", no_proxy)?;
+    }
+    let headers = cx.empty_array();
+    for (i, (name, value)) in proxy.headers.iter().enumerate() {
+        let tuple = cx.empty_array();
+        let name = cx.string(name);
+        tuple.set(cx, 0, name)?;
+        let value = cx.string(value);
+        tuple.set(cx, 1, value)?;
+        headers.set(cx, i as u32, tuple)?;
+    }
+    obj.set(cx, "headers", headers)?;
+
+    Ok(obj.upcast())
+}
+
+// Read the `proxyPool` handle of an options object
+fn parse_proxy_pool(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+) -> NeonResult<Option<Arc<ProxyPool>>> {
+    let pool = obj
+        .get_opt(cx, "proxyPool")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsBox<Arc<ProxyPool>>, _>(cx).ok())
+        .map(|pool| Arc::clone(&**pool));
+    Ok(pool)
+}
+
+// How long resolver answers are cached per host unless `proxyResolverCacheTtl` says otherwise
+const PROXY_RESOLVER_CACHE_TTL: Duration = Duration::from_secs(300);
+
+// Read `proxyResolver` (a function, or `null` to remove it) and `proxyResolverCacheTtl`.
+// The function is called as `(url, done)` and must call `done(error, proxy)` exactly once.
+fn parse_proxy_resolver(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+) -> NeonResult<Option<Option<Arc<ProxyResolver>>>> {
+    let callback = match obj.get_opt::<JsValue, _, _>(cx, "proxyResolver")? {
+        Some(value) if value.is_a::<JsNull, _>(cx) => return Ok(Some(None)),
+        Some(value) => value.downcast_or_throw::<JsFunction, _>(cx)?,
+        None => return Ok(None),
+    };
+    let cache_ttl = match parse_limit(cx, obj, "proxyResolverCacheTtl")? {
+        Some(ttl) => ttl.map(Duration::from_millis),
+        None => Some(PROXY_RESOLVER_CACHE_TTL),
+    };
+
+    let callback = Arc::new(callback.root(cx));
+    let mut channel = cx.channel();
+    // Requests waiting on an answer keep the process alive through their own promises
+    channel.unref(cx);
+
+    let resolve = move |url: String, reply: oneshot::Sender<Resolution>| {
+        let callback = callback.clone();
+        channel.send(move |mut cx| {
+            let reply = Mutex::new(Some(reply));
+            let done = JsFunction::new(&mut cx, move |mut cx| {
+                let error = cx
+                    .argument_opt(0)
+                    .and_then(|v| v.downcast::<JsString, _>(&mut cx).ok())
+                    .map(|v| v.value(&mut cx));
+                let proxy = cx
+                    .argument_opt(1)
+                    .and_then(|v| v.downcast::<JsString, _>(&mut cx).ok())
+                    .map(|v| v.value(&mut cx));
+                if let Some(reply) = reply.lock().unwrap().take() {
+                    let _ = reply.send(match error {
+                        Some(error) => Err(anyhow::anyhow!(error)),
+                        None => Ok(proxy),
+                    });
+                }
+                Ok(cx.undefined())
+            })?;
+
+            let cb = callback.to_inner(&mut cx);
+            let this = cx.undefined();
+            let url = cx.string(url);
+            cb.call(&mut cx, this, vec![url.upcast(), done.upcast()])?;
+            Ok(())
+        });
+    };
+
+    let resolver = ProxyResolver::new(Box::new(resolve), cache_ttl);
+    Ok(Some(Some(Arc::new(resolver))))
+}
+
+// Read `beforeRequest` (a function, or `null` to remove it). The function is called as
+// `(request, done)` and must call `done(error, request, response)` exactly once: with the request
+// to send, possibly changed, or with a response to return without sending it.
+fn parse_before_request(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+) -> NeonResult<Option<Option<Arc<BeforeRequestFn>>>> {
+    let callback = match obj.get_opt::<JsValue, _, _>(cx, "beforeRequest")? {
+        Some(value) if value.is_a::<JsNull, _>(cx) => return Ok(Some(None)),
+        Some(value) => value.downcast_or_throw::<JsFunction, _>(cx)?,
+        None => return Ok(None),
+    };
+
+    let callback = Arc::new(callback.root(cx));
+    let mut channel = cx.channel();
+    // Requests waiting on a hook keep the process alive through their own promises
+    channel.unref(cx);
+
+    let hook = move |request: HookRequest, reply: oneshot::Sender<anyhow::Result<Intercept>>| {
+        let callback = callback.clone();
+        channel.send(move |mut cx| {
+            let url = request.url.clone();
+            let reply = Mutex::new(Some(reply));
+            let done = JsFunction::new(&mut cx, move |mut cx| {
+                let intercept = cx.try_catch(|cx| {
+                    let response = cx
+                        .argument_opt(2)
+                        .and_then(|v| v.downcast::<JsObject, _>(cx).ok());
+                    if let Some(response) = response {
+                        return js_object_to_response(cx, response, &url).map(Intercept::Respond);
+                    }
+                    let request = cx.argument::<JsObject>(1)?;
+                    js_object_to_hook_request(cx, request).map(Intercept::Send)
+                });
+                let result = hook_result(&mut cx, intercept)?;
+                if let Some(reply) = reply.lock().unwrap().take() {
+                    let _ = reply.send(result);
+                }
+                Ok(cx.undefined())
+            })?;
+
+            let cb = callback.to_inner(&mut cx);
+            let this = cx.undefined();
+            let request = hook_request_to_js_object(&mut cx, &request)?;
+            cb.call(&mut cx, this, vec![request.upcast(), done.upcast()])?;
+            Ok(())
+        });
+    };
+
+    Ok(Some(Some(Arc::new(hook))))
+}
+
+// Read `afterResponse` (a function, or `null` to remove it). The function is called as
+// `(request, response, done)` and must call `done(error, response)` exactly once.
+fn parse_after_response(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+) -> NeonResult<Option<Option<Arc<AfterResponseFn>>>> {
+    let callback = match obj.get_opt::<JsValue, _, _>(cx, "afterResponse")? {
+        Some(value) if value.is_a::<JsNull, _>(cx) => return Ok(Some(None)),
+        Some(value) => value.downcast_or_throw::<JsFunction, _>(cx)?,
+        None => return Ok(None),
+    };
+
+    let callback = Arc::new(callback.root(cx));
+    let mut channel = cx.channel();
+    channel.unref(cx);
+
+    let hook = move |request: HookRequest,
+                     response: Response,
+                     reply: oneshot::Sender<anyhow::Result<Response>>| {
+        let callback = callback.clone();
+        channel.send(move |mut cx| {
+            let url = response.url.clone();
+            let reply = Mutex::new(Some(reply));
+            let done = JsFunction::new(&mut cx, move |mut cx| {
+                let response = cx.try_catch(|cx| {
+                    let response = cx.argument::<JsObject>(1)?;
+                    js_object_to_response(cx, response, &url)
+                });
+                let result = hook_result(&mut cx, response)?;
+                if let Some(reply) = reply.lock().unwrap().take() {
+                    let _ = reply.send(result);
+                }
+                Ok(cx.undefined())
+            })?;
+
+            let cb = callback.to_inner(&mut cx);
+            let this = cx.undefined();
+            let request = hook_request_to_js_object(&mut cx, &request)?;
+            let response = response_to_js_object(&mut cx, response)?;
+            let args = vec![request.upcast(), response.upcast(), done.upcast()];
+            cb.call(&mut cx, this, args)?;
+            Ok(())
+        });
+    };
+
+    Ok(Some(Some(Arc::new(hook))))
+}
+
+// What a hook passed to `done`: the error it gave, or what was read from the rest of the
+// arguments, where a value of the wrong shape is an error too
+fn hook_result<'a, T>(
+    cx: &mut FunctionContext<'a>,
+    value: Result<T, Handle<'a, JsValue>>,
+) -> NeonResult<anyhow::Result<T>> {
+    let error = cx
+        .argument_opt(0)
+        .and_then(|v| v.downcast::<JsString, _>(cx).ok())
+        .map(|v| v.value(cx));
+    if let Some(error) = error {
+        return Ok(Err(anyhow::anyhow!(error)));
+    }
+    match value {
+        Ok(value) => Ok(Ok(value)),
+        Err(thrown) => {
+            let message = thrown.to_string(cx)?.value(cx);
+            Ok(Err(anyhow::anyhow!(message)))
+        }
+    }
+}
+
+fn hook_request_to_js_object<'a, C: Context<'a>>(
+    cx: &mut C,
+    request: &HookRequest,
+) -> JsResult<'a, JsObject> {
+    let obj = cx.empty_object();
+
+    let method = cx.string(&request.method);
+    obj.set(cx, "method", method)?;
+    let url = cx.string(&request.url);
+    obj.set(cx, "url", url)?;
+
+    let headers = cx.empty_object();
+    for (key, value) in &request.headers {
+        let value = cx.string(value);
+        headers.set(cx, key.as_str(), value)?;
+    }
+    obj.set(cx, "headers", headers)?;
+
+    if let Some(body) = &request.body {
+        let body = cx.string(body);
+        obj.set(cx, "body", body)?;
+    }
+
+    Ok(obj)
+}
+
+// Read `{ method, url, headers, body }` back from a before-request hook
+fn js_object_to_hook_request(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+) -> NeonResult<HookRequest> {
+    let method: Handle<JsString> = obj.get(cx, "method")?;
+    let url: Handle<JsString> = obj.get(cx, "url")?;
+    let headers = match obj.get_opt(cx, "headers")? {
+        Some(value) => parse_headers_from_value(cx, value)?,
+        None => IndexMap::new(),
+    };
+    let body = obj
+        .get_opt(cx, "body")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(cx).ok())
+        .map(|v| v.value(cx));
+
+    Ok(HookRequest {
+        method: method.value(cx).to_uppercase(),
+        url: url.value(cx),
+        headers,
+        body,
+    })
+}
+
+// Read a response made or changed by a hook: `{ status, headers, body, url, cookies }`, where
+// a missing status is 200 and a missing URL is `url`
+fn js_object_to_response(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+    url: &str,
+) -> NeonResult<Response> {
+    let status = match obj.get_opt::<JsNumber, _, _>(cx, "status")? {
+        Some(status) => status.value(cx),
+        None => 200.0,
+    };
+    if !(100.0..=999.0).contains(&status) || status.fract() != 0.0 {
+        return cx.throw_range_error(format!("Invalid response status {}", status));
+    }
+    let headers = match obj.get_opt(cx, "headers")? {
+        Some(value) => parse_headers_from_value(cx, value)?,
+        None => IndexMap::new(),
+    };
+    let cookies = match obj.get_opt(cx, "cookies")? {
+        Some(value) => parse_headers_from_value(cx, value)?,
+        None => IndexMap::new(),
+    };
+    let body = obj
+        .get_opt(cx, "body")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(cx).ok())
+        .map(|v| v.value(cx))
+        .unwrap_or_default();
+    let url = obj
+        .get_opt(cx, "url")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(cx).ok())
+        .map(|v| v.value(cx))
+        .unwrap_or_else(|| url.to_string());
+
+    Ok(Response {
+        status: status as u16,
+        headers,
+        body,
+        cookies,
+        url,
+        proxy: None,
+        cache_status: None,
+    })
+}
+
+// Create a proxy pool from { proxies, strategy, cooldown, maxFailures }
+fn create_proxy_pool(mut cx: FunctionContext) -> JsResult<JsBox<Arc<ProxyPool>>> {
+    let options = cx.argument::<JsObject>(0)?;
+    let mut config = ProxyPoolConfig::default();
+
+    let proxies: Handle<JsArray> = options.get(&mut cx, "proxies")?;
+    for value in proxies.to_vec(&mut cx)? {
+        let proxy = value.downcast_or_throw::<JsString, _>(&mut cx)?;
+        config.proxies.push(proxy.value(&mut cx));
+    }
+
+    let strategy = options
+        .get_opt(&mut cx, "strategy")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(&mut cx).ok())
+        .map(|v| v.value(&mut cx));
+    if let Some(strategy) = strategy {
+        config.strategy = match RotationStrategy::parse(&strategy) {
+            Some(strategy) => strategy,
+            None => {
+                return cx
+                    .throw_type_error(format!("Unknown proxy rotation strategy '{}'", strategy))
+            }
+        };
+    }
+
+    if let Some(cooldown) = parse_limit(&mut cx, options, "cooldown")?.flatten() {
+        config.cooldown = cooldown;
+    }
+    if let Some(max_failures) = parse_limit(&mut cx, options, "maxFailures")?.flatten() {
+        config.max_failures = max_failures.min(u32::MAX as u64) as u32;
+    }
+
+    match ProxyPool::new(config) {
+        Ok(pool) => Ok(cx.boxed(Arc::new(pool))),
+        Err(e) => {
+            let msg = format!("{:#}", e);
+            cx.throw_error(msg)
+        }
+    }
+}
+
+fn proxy_pool_status(mut cx: FunctionContext) -> JsResult<JsArray> {
+    let pool = cx.argument::<JsBox<Arc<ProxyPool>>>(0)?;
+    let statuses = pool.status();
+    let js_array = cx.empty_array();
+
+    for (i, status) in statuses.into_iter().enumerate() {
+        let obj = cx.empty_object();
+        let proxy = cx.string(status.proxy);
+        obj.set(&mut cx, "proxy", proxy)?;
+        let healthy = cx.boolean(status.healthy);
+        obj.set(&mut cx, "healthy", healthy)?;
+        let successes = cx.number(status.successes as f64);
+        obj.set(&mut cx, "successes", successes)?;
+        let failures = cx.number(status.failures as f64);
+        obj.set(&mut cx, "failures", failures)?;
+        let cooldown: Handle<JsValue> = match status.cooldown_remaining {
+            Some(remaining) => cx.number(remaining.as_millis() as f64).upcast(),
+            None => cx.null().upcast(),
+        };
+        obj.set(&mut cx, "cooldownRemaining", cooldown)?;
+        js_array.set(&mut cx, i as u32, obj)?;
+    }
+
+    Ok(js_array)
+}
+
+// Create a HAR recorder: ({ maxBodySize }?). `maxBodySize: null` keeps bodies whole.
+fn create_har_recorder(mut cx: FunctionContext) -> JsResult<JsBox<Arc<Recorder>>> {
+    let mut options = RecorderOptions::default();
+    let obj = cx
+        .argument_opt(0)
+        .and_then(|v| v.downcast::<JsObject, _>(&mut cx).ok());
+    let max_body_size = match obj {
+        Some(obj) => parse_limit(&mut cx, obj, "maxBodySize")?,
+        None => None,
+    };
+    if let Some(max_body_size) = max_body_size {
+        options.max_body_size = max_body_size.map(|max| max as usize);
+    }
+    Ok(cx.boxed(Arc::new(Recorder::new(options))))
+}
+
+// The recorded entries as a HAR 1.2 JSON string
+fn har_recorder_export(mut cx: FunctionContext) -> JsResult<JsString> {
+    let recorder = cx.argument::<JsBox<Arc<Recorder>>>(0)?;
+    match recorder.to_har() {
+        Ok(har) => Ok(cx.string(har)),
+        Err(e) => cx.throw_error(format!("{:#}", e)),
+    }
+}
+
+fn har_recorder_clear(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+    let recorder = cx.argument::<JsBox<Arc<Recorder>>>(0)?;
+    recorder.clear();
+    Ok(cx.undefined())
+}
+
+fn har_recorder_count(mut cx: FunctionContext) -> JsResult<JsNumber> {
+    let recorder = cx.argument::<JsBox<Arc<Recorder>>>(0)?;
+    Ok(cx.number(recorder.entry_count() as f64))
+}
+
+// Record every request, through a session or not: (recorder | null)
+fn set_global_har_recorder(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+    let value = cx.argument::<JsValue>(0)?;
+    let recorder = parse_recorder_value(&mut cx, value)?;
+    set_global_recorder(recorder);
+    Ok(cx.undefined())
+}
+
+// Read `recorder`, a handle from `createHarRecorder`, or `null` to remove it
+fn parse_recorder(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+) -> NeonResult<Option<Option<Arc<Recorder>>>> {
+    match obj.get_opt::<JsValue, _, _>(cx, "recorder")? {
+        Some(value) => parse_recorder_value(cx, value).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn parse_recorder_value(
+    cx: &mut FunctionContext,
+    value: Handle<JsValue>,
+) -> NeonResult<Option<Arc<Recorder>>> {
+    if value.is_a::<JsNull, _>(cx) {
+        return Ok(None);
+    }
+    let recorder = value.downcast_or_throw::<JsBox<Arc<Recorder>>, _>(cx)?;
+    Ok(Some(Arc::clone(&**recorder)))
+}
+
+// Create a cassette: ({ mode, contents?, matchBody?, ignoreQueryParams?, allowRepeats? }).
+// `contents` is a saved cassette, required to replay.
+fn create_cassette(mut cx: FunctionContext) -> JsResult<JsBox<Arc<Cassette>>> {
+    let options = cx.argument::<JsObject>(0)?;
+
+    let mode: Handle<JsString> = options.get(&mut cx, "mode")?;
+    let mode = mode.value(&mut cx);
+    let mode = match CassetteMode::parse(&mode) {
+        Some(mode) => mode,
+        None => return cx.throw_type_error(format!("Unknown cassette mode '{}'", mode)),
+    };
+
+    let contents = options
+        .get_opt(&mut cx, "contents")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(&mut cx).ok())
+        .map(|v| v.value(&mut cx));
+
+    let mut rules = MatchRules::default();
+    if let Some(body) = options
+        .get_opt(&mut cx, "matchBody")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsBoolean, _>(&mut cx).ok())
+    {
+        rules.body = body.value(&mut cx);
+    }
+    if let Some(allow_repeats) = options
+        .get_opt(&mut cx, "allowRepeats")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsBoolean, _>(&mut cx).ok())
+    {
+        rules.allow_repeats = allow_repeats.value(&mut cx);
+    }
+    if let Some(params) = options
+        .get_opt(&mut cx, "ignoreQueryParams")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsArray, _>(&mut cx).ok())
+    {
+        for value in params.to_vec(&mut cx)? {
+            let name = value.downcast_or_throw::<JsString, _>(&mut cx)?;
+            rules.ignore_query_params.push(name.value(&mut cx));
+        }
+    }
+
+    match Cassette::new(mode, rules, contents.as_deref()) {
+        Ok(cassette) => Ok(cx.boxed(Arc::new(cassette))),
+        Err(e) => cx.throw_error(format!("{:#}", e)),
+    }
+}
+
+// The cassette's interactions as JSON, in the format `createCassette` reads back
+fn cassette_export(mut cx: FunctionContext) -> JsResult<JsString> {
+    let cassette = cx.argument::<JsBox<Arc<Cassette>>>(0)?;
+    match cassette.to_json() {
+        Ok(json) => Ok(cx.string(json)),
+        Err(e) => cx.throw_error(format!("{:#}", e)),
+    }
+}
+
+fn cassette_count(mut cx: FunctionContext) -> JsResult<JsNumber> {
+    let cassette = cx.argument::<JsBox<Arc<Cassette>>>(0)?;
+    Ok(cx.number(cassette.interaction_count() as f64))
+}
+
+// Record or replay every request and WebSocket without a cassette of its own: (cassette | null)
+fn set_global_cassette_handle(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+    let value = cx.argument::<JsValue>(0)?;
+    let cassette = parse_cassette_value(&mut cx, value)?;
+    set_global_cassette(cassette);
+    Ok(cx.undefined())
+}
+
+// Read `cassette`, a handle from `createCassette`, or `null` to remove it
+fn parse_cassette(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+) -> NeonResult<Option<Option<Arc<Cassette>>>> {
+    match obj.get_opt::<JsValue, _, _>(cx, "cassette")? {
+        Some(value) => parse_cassette_value(cx, value).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn parse_cassette_value(
+    cx: &mut FunctionContext,
+    value: Handle<JsValue>,
+) -> NeonResult<Option<Arc<Cassette>>> {
+    if value.is_a::<JsNull, _>(cx) {
+        return Ok(None);
+    }
+    let cassette = value.downcast_or_throw::<JsBox<Arc<Cassette>>, _>(cx)?;
+    Ok(Some(Arc::clone(&**cassette)))
+}
+
+fn pick_profile(mut cx: FunctionContext) -> JsResult<JsString> {
+    let selector = cx.argument::<JsBox<ProfileSelector>>(0)?;
+    let name = selector.pick();
+    Ok(cx.string(name))
+}
+
+fn create_session(mut cx: FunctionContext) -> JsResult<JsString> {
+    let options_value = cx.argument_opt(0);
+
+    let (session_id_opt, browser_opt, proxy_opt, settings) = if let Some(value) = options_value {
+        if value.is_a::<JsUndefined, _>(&mut cx) || value.is_a::<JsNull, _>(&mut cx) {
+            (None, None, None, EmulationSettings::default())
+        } else {
+            let obj = value.downcast_or_throw::<JsObject, _>(&mut cx)?;
+            let session_id = obj
+                .get_opt(&mut cx, "sessionId")?
+                .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(&mut cx).ok())
+                .map(|v| v.value(&mut cx));
+            let browser = obj
+                .get_opt(&mut cx, "browser")?
+                .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(&mut cx).ok())
+                .map(|v| v.value(&mut cx));
+            let proxy = match obj.get_opt::<JsValue, _, _>(&mut cx, "proxy")? {
+                Some(value) => parse_proxy(&mut cx, value)?,
+                None => None,
+            };
+            let settings = parse_emulation_settings(&mut cx, obj)?;
+            (session_id, browser, proxy, settings)
+        }
+    } else {
+        (None, None, None, EmulationSettings::default())
+    };
+
+    let options_obj = options_value.and_then(|v| v.downcast::<JsObject, _>(&mut cx).ok());
+
+    // Sessions flagged `neverExpire` are exempt from TTL, idle and capacity eviction
+    let never_expire = match options_obj {
+        Some(obj) => obj
+            .get_opt(&mut cx, "neverExpire")?
+            .and_then(|v: Handle<JsValue>| v.downcast::<JsBoolean, _>(&mut cx).ok())
+            .map(|v| v.value(&mut cx))
+            .unwrap_or(false),
+        None => false,
+    };
+
+    let proxy_pool = match options_obj {
+        Some(obj) => parse_proxy_pool(&mut cx, obj)?,
+        None => None,
+    };
+
+    let proxy_resolver = match options_obj {
+        Some(obj) => parse_proxy_resolver(&mut cx, obj)?.flatten(),
+        None => None,
+    };
+
+    let connection_pool = match options_obj {
+        Some(obj) => parse_connection_pool(&mut cx, obj)?.unwrap_or_default(),
+        None => PoolSettings::default(),
+    };
+
+    let rate_limits = match options_obj {
+        Some(obj) => parse_rate_limits(&mut cx, obj)?.unwrap_or_default(),
+        None => RateLimits::default(),
+    };
+
+    let cache = match options_obj {
+        Some(obj) => parse_cache(&mut cx, obj)?.flatten(),
+        None => None,
+    };
+
+    let recorder = match options_obj {
+        Some(obj) => parse_recorder(&mut cx, obj)?.flatten(),
+        None => None,
+    };
+
+    let cassette = match options_obj {
+        Some(obj) => parse_cassette(&mut cx, obj)?.flatten(),
+        None => None,
+    };
+
+    let hooks = match options_obj {
+        Some(obj) => Hooks {
+            before_request: parse_before_request(&mut cx, obj)?.flatten(),
+            after_response: parse_after_response(&mut cx, obj)?.flatten(),
+        },
+        None => Hooks::default(),
+    };
+
+    // Default headers merged into every request made through the session
+    let headers = match options_obj {
+        Some(obj) => match obj.get_opt(&mut cx, "headers")? {
+            Some(value) => parse_headers_from_value(&mut cx, value)?,
+            None => IndexMap::new(),
+        },
+        None => IndexMap::new(),
+    };
+
+    let session_id = session_id_opt.unwrap_or_else(generate_session_id);
+    let emulation = parse_emulation(&mut cx, browser_opt, settings)?;
+
+    let config = SessionConfig::new(
+        emulation,
+        proxy_opt,
+        proxy_pool,
+        proxy_resolver,
+        connection_pool,
+        rate_limits,
+        cache,
+    );
+    let extras = SessionExtras {
+        recorder,
+        cassette,
+        hooks,
+    };
+    let created = create_managed_session(session_id, config, never_expire, headers, extras);
+
+    match created {
+        Ok(id) => Ok(cx.string(id)),
+        Err(e) => throw_request_error(&mut cx, e, None),
+    }
+}
+
+// Reset a session's cookies, connections or both: (sessionId, "cookies" | "connections" | "all")
+fn reset_session(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+    let session_id = cx.argument::<JsString>(0)?.value(&mut cx);
+    let mode = cx
+        .argument_opt(1)
+        .and_then(|v| v.downcast::<JsString, _>(&mut cx).ok())
+        .map(|v| v.value(&mut cx));
+    let mode = match mode {
+        Some(mode) => match ResetMode::parse(&mode) {
+            Some(mode) => mode,
+            None => return cx.throw_type_error(format!("Unknown session reset mode '{}'", mode)),
+        },
+        None => ResetMode::All,
+    };
+
+    if let Err(e) = reset_managed_session(&session_id, mode) {
+        return throw_request_error(&mut cx, e, None);
+    }
+
+    Ok(cx.undefined())
+}
+
+// Swap the profile and/or proxy of a live session, keeping its cookie jar.
+// `browser` replaces the emulation (with os/skip flags); `proxy: null` removes the proxy,
+// as do `proxyPool: null` and `proxyResolver: null` for those.
+fn reconfigure_session(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+    let session_id = cx.argument::<JsString>(0)?.value(&mut cx);
+    let options = cx.argument::<JsObject>(1)?;
+
+    let browser = options
+        .get_opt(&mut cx, "browser")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(&mut cx).ok())
+        .map(|v| v.value(&mut cx));
+
+    let emulation = match browser {
+        Some(browser) => {
+            let settings = parse_emulation_settings(&mut cx, options)?;
+            Some(parse_emulation(&mut cx, Some(browser), settings)?)
+        }
+        None => None,
+    };
+
+    let proxy = match options.get_opt::<JsValue, _, _>(&mut cx, "proxy")? {
+        Some(value) => Some(parse_proxy(&mut cx, value)?),
+        None => None,
+    };
+
+    let proxy_pool = match options.get_opt::<JsValue, _, _>(&mut cx, "proxyPool")? {
+        Some(value) if value.is_a::<JsNull, _>(&mut cx) => Some(None),
+        Some(value) => {
+            let pool = value.downcast_or_throw::<JsBox<Arc<ProxyPool>>, _>(&mut cx)?;
+            Some(Some(Arc::clone(&**pool)))
+        }
+        None => None,
+    };
+
+    let proxy_resolver = parse_proxy_resolver(&mut cx, options)?;
+    let connection_pool = parse_connection_pool(&mut cx, options)?;
+    let rate_limits = parse_rate_limits(&mut cx, options)?;
+    let cache = parse_cache(&mut cx, options)?;
+    let recorder = parse_recorder(&mut cx, options)?;
+    let cassette = parse_cassette(&mut cx, options)?;
+    let before_request = parse_before_request(&mut cx, options)?;
+    let after_response = parse_after_response(&mut cx, options)?;
+
+    let update = SessionUpdate {
+        emulation,
+        proxy,
+        proxy_pool,
+        proxy_resolver,
+        connection_pool,
+        rate_limits,
+        cache,
+        recorder,
+        cassette,
+        before_request,
+        after_response,
+    };
+    if let Err(e) = reconfigure_managed_session(&session_id, update) {
+        return throw_request_error(&mut cx, e, None);
+    }
+
+    Ok(cx.undefined())
+}
+
+// Read `connectionPool: { maxIdlePerHost, idleTimeout, maxSize }`. Limits left out keep
+// their defaults and `null` removes them.
+fn parse_connection_pool(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+) -> NeonResult<Option<PoolSettings>> {
+    let Some(pool) = obj
+        .get_opt(cx, "connectionPool")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsObject, _>(cx).ok())
+    else {
+        return Ok(None);
+    };
+
+    let mut settings = PoolSettings::default();
+    if let Some(max_idle) = parse_limit(cx, pool, "maxIdlePerHost")? {
+        settings.max_idle_per_host = max_idle.map(|max| max as usize);
+    }
+    if let Some(idle_timeout) = parse_limit(cx, pool, "idleTimeout")? {
+        settings.idle_timeout = idle_timeout;
+    }
+    if let Some(max_size) = parse_limit(cx, pool, "maxSize")? {
+        // wreq reads a zero pool size as no limit; use null for that instead
+        if max_size == Some(0) {
+            return cx.throw_range_error("maxSize must be at least 1, or null for no limit");
+        }
+        settings.max_size = max_size.map(|max| max.min(u32::MAX as u64) as u32);
+    }
+    Ok(Some(settings))
+}
+
+// Read `rateLimits: { maxConcurrent, requestsPerSecond, bytesPerSecond }`, replacing every
+// limit; `null` removes them all
+fn parse_rate_limits(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+) -> NeonResult<Option<RateLimits>> {
+    let Some(value) = obj.get_opt::<JsValue, _, _>(cx, "rateLimits")? else {
+        return Ok(None);
+    };
+    rate_limits_from_value(cx, value).map(Some)
+}
+
+fn rate_limits_from_value(
+    cx: &mut FunctionContext,
+    value: Handle<JsValue>,
+) -> NeonResult<RateLimits> {
+    if value.is_a::<JsNull, _>(cx) {
+        return Ok(RateLimits::default());
+    }
+    let limits = value.downcast_or_throw::<JsObject, _>(cx)?;
 
-    for i in 0..len {
-        let element: Handle<JsValue> = array.get(cx, i)?;
-        let tuple = element.downcast::<JsArray, _>(cx).or_throw(cx)?;
-        let (name, value) = parse_header_tuple(cx, tuple)?;
-        headers.insert(name, value);
+    let max_concurrent = parse_limit(cx, limits, "maxConcurrent")?.flatten();
+    if max_concurrent == Some(0) {
+        return cx.throw_range_error("maxConcurrent must be at least 1 or null");
     }
 
-    Ok(headers)
+    Ok(RateLimits {
+        max_concurrent: max_concurrent.map(|max| max as usize),
+        requests_per_second: parse_rate(cx, limits, "requestsPerSecond")?,
+        bytes_per_second: parse_rate(cx, limits, "bytesPerSecond")?,
+    })
 }
 
-fn parse_headers_from_object(cx: &mut FunctionContext, obj: Handle<JsObject>) -> NeonResult<IndexMap<String, String>> {
-    let mut headers = IndexMap::new();
-    let keys = obj.get_own_property_names(cx)?;
-    let keys_vec = keys.to_vec(cx)?;
+// Read a rate, which may be fractional; `undefined` and `null` mean no limit
+fn parse_rate(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+    key: &str,
+) -> NeonResult<Option<f64>> {
+    let value = match obj.get_opt::<JsValue, _, _>(cx, key)? {
+        Some(value) if !value.is_a::<JsNull, _>(cx) => value,
+        _ => return Ok(None),
+    };
 
-    for key_val in keys_vec {
-        if let Ok(key_str) = key_val.downcast::<JsString, _>(cx) {
-            let key = key_str.value(cx);
-            let value = obj.get(cx, key.as_str())?;
-            let value = coerce_header_value(cx, value)?;
-            headers.insert(key, value);
-        }
+    let rate = value.downcast_or_throw::<JsNumber, _>(cx)?.value(cx);
+    if !rate.is_finite() || rate < MIN_RATE {
+        return cx.throw_range_error(format!(
+            "{} must be at least {} per second, or null for no limit",
+            key, MIN_RATE
+        ));
     }
 
-    Ok(headers)
+    Ok(Some(rate))
 }
 
-fn parse_headers_from_value(cx: &mut FunctionContext, value: Handle<JsValue>) -> NeonResult<IndexMap<String, String>> {
-    if value.is_a::<JsUndefined, _>(cx) || value.is_a::<JsNull, _>(cx) {
-        return Ok(IndexMap::new());
+// Read `cache: { store: "memory", maxSize } | { store: "disk", path }`; `null` removes the
+// cache
+fn parse_cache(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+) -> NeonResult<Option<Option<CacheSettings>>> {
+    let Some(value) = obj.get_opt::<JsValue, _, _>(cx, "cache")? else {
+        return Ok(None);
+    };
+    if value.is_a::<JsNull, _>(cx) {
+        return Ok(Some(None));
     }
+    let cache = value.downcast_or_throw::<JsObject, _>(cx)?;
 
-    if value.is_a::<JsArray, _>(cx) {
-        let array = value.downcast::<JsArray, _>(cx).or_throw(cx)?;
-        return parse_headers_from_array(cx, array);
-    }
+    let store = cache
+        .get_opt(cx, "store")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(cx).ok())
+        .map(|v| v.value(cx));
+    let settings = match store.as_deref() {
+        None | Some("memory") => match parse_limit(cx, cache, "maxSize")?.flatten() {
+            Some(max_size) => CacheSettings::Memory { max_size },
+            None => CacheSettings::default(),
+        },
+        Some("disk") => {
+            let path: Handle<JsString> = cache.get(cx, "path")?;
+            CacheSettings::Disk {
+                path: path.value(cx),
+            }
+        }
+        Some(store) => return cx.throw_type_error(format!("Unknown cache store '{}'", store)),
+    };
 
-    if value.is_a::<JsObject, _>(cx) {
-        let obj = value.downcast::<JsObject, _>(cx).or_throw(cx)?;
-        return parse_headers_from_object(cx, obj);
-    }
+    Ok(Some(Some(settings)))
+}
 
-    cx.throw_type_error("headers must be an array or object")
+// Limit every host's requests, across sessions and plain fetch calls: (limits | null)
+fn configure_host_rate_limits(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+    let value = cx.argument::<JsValue>(0)?;
+    let limits = rate_limits_from_value(&mut cx, value)?;
+    configure_host_limits(limits);
+    Ok(cx.undefined())
 }
 
-// Convert JS object to RequestOptions
-fn js_object_to_request_options(
+// Read a limit where `undefined` keeps the current value and `null` removes the limit
+fn parse_limit(
     cx: &mut FunctionContext,
     obj: Handle<JsObject>,
-) -> NeonResult<RequestOptions> {
-    // Get URL (required)
-    let url: Handle<JsString> = obj.get(cx, "url")?;
-    let url = url.value(cx);
+    key: &str,
+) -> NeonResult<Option<Option<u64>>> {
+    let value = match obj.get_opt::<JsValue, _, _>(cx, key)? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
 
-    // Get browser (optional, defaults to chrome_142)
-    let browser_str = obj
-        .get_opt(cx, "browser")?
-        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(cx).ok())
-        .map(|v| v.value(cx))
-        .unwrap_or_else(|| "chrome_142".to_string());
+    if value.is_a::<JsNull, _>(cx) {
+        return Ok(Some(None));
+    }
 
-    let emulation = parse_emulation(&browser_str);
+    let number = value.downcast_or_throw::<JsNumber, _>(cx)?.value(cx);
+    if !number.is_finite() || number < 0.0 {
+        return cx.throw_range_error(format!("{} must be a non-negative number or null", key));
+    }
 
-    // Get method (optional, defaults to GET)
-    let method = obj
-        .get_opt(cx, "method")?
-        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(cx).ok())
-        .map(|v| v.value(cx))
-        .unwrap_or_else(|| "GET".to_string());
+    Ok(Some(Some(number as u64)))
+}
 
-    // Get headers (optional)
-    let headers = if let Ok(Some(headers_val)) = obj.get_opt(cx, "headers") {
-        parse_headers_from_value(cx, headers_val)?
-    } else {
-        IndexMap::new()
-    };
+// Configure session expiry/capacity and the eviction callback:
+// { timeToLive, timeToIdle, maxSessions, onEvict }
+fn configure_sessions(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+    let options = cx.argument::<JsObject>(0)?;
+    let mut limits = session_limits();
 
-    // Get body (optional)
-    let body = obj
-        .get_opt(cx, "body")?
-        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(cx).ok())
-        .map(|v| v.value(cx));
+    if let Some(ttl) = parse_limit(&mut cx, options, "timeToLive")? {
+        limits.time_to_live = ttl.map(Duration::from_millis);
+    }
+    if let Some(tti) = parse_limit(&mut cx, options, "timeToIdle")? {
+        limits.time_to_idle = tti.map(Duration::from_millis);
+    }
+    if let Some(max_sessions) = parse_limit(&mut cx, options, "maxSessions")? {
+        limits.max_sessions = max_sessions;
+    }
 
-    // Get proxy (optional)
-    let proxy = obj
-        .get_opt(cx, "proxy")?
-        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(cx).ok())
-        .map(|v| v.value(cx));
+    match options.get_opt::<JsValue, _, _>(&mut cx, "onEvict")? {
+        Some(value) if value.is_a::<JsNull, _>(&mut cx) => set_eviction_handler(None),
+        Some(value) => {
+            let callback = value.downcast_or_throw::<JsFunction, _>(&mut cx)?;
+            let callback = Arc::new(callback.root(&mut cx));
+            let mut channel = cx.channel();
+            // Don't keep the process alive just to report evictions
+            channel.unref(&mut cx);
+
+            set_eviction_handler(Some(Arc::new(move |eviction: SessionEviction| {
+                let callback = callback.clone();
+                channel.send(move |mut cx| {
+                    let cb = callback.to_inner(&mut cx);
+                    let this = cx.undefined();
+                    let event = cx.empty_object();
+                    let session_id = cx.string(eviction.session_id);
+                    event.set(&mut cx, "sessionId", session_id)?;
+                    let reason = cx.string(eviction.cause.as_str());
+                    event.set(&mut cx, "reason", reason)?;
+                    let browser = cx.string(eviction.browser);
+                    event.set(&mut cx, "browser", browser)?;
+                    let proxy: Handle<JsValue> = match eviction.proxy {
+                        Some(proxy) => cx.string(proxy).upcast(),
+                        None => cx.null().upcast(),
+                    };
+                    event.set(&mut cx, "proxy", proxy)?;
+                    cb.call(&mut cx, this, vec![event.upcast()])?;
+                    Ok(())
+                });
+            })));
+        }
+        None => {}
+    }
 
-    // Get timeout (optional, defaults to 30000ms)
-    let timeout = obj
-        .get_opt(cx, "timeout")?
-        .and_then(|v: Handle<JsValue>| v.downcast::<JsNumber, _>(cx).ok())
-        .map(|v| v.value(cx) as u64)
-        .unwrap_or(30000);
+    configure_session_limits(limits);
 
-    // Get sessionId (optional)
-    let session_id = obj
-        .get_opt(cx, "sessionId")?
-        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(cx).ok())
-        .map(|v| v.value(cx))
-        .filter(|v| !v.trim().is_empty())
-        .unwrap_or_else(generate_session_id);
+    Ok(cx.undefined())
+}
 
-    let ephemeral = obj
-        .get_opt(cx, "ephemeral")?
-        .and_then(|v: Handle<JsValue>| v.downcast::<JsBoolean, _>(cx).ok())
-        .map(|v| v.value(cx))
-        .unwrap_or(false);
+// Configure native logging: { level, targets, batchSize, flushInterval, logger, file }.
+// Omitted fields keep their current value; a null `logger` or `file` stops delivering events.
+fn configure_native_logging(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+    let options = cx.argument::<JsObject>(0)?;
+    let mut settings = log_settings();
+
+    if let Some(level) = options.get_opt::<JsString, _, _>(&mut cx, "level")? {
+        let level = level.value(&mut cx);
+        settings.level = match parse_level(&level) {
+            Some(level) => level,
+            None => return cx.throw_type_error(format!("Unknown log level '{}'", level)),
+        };
+    }
+    if let Some(targets) = options.get_opt::<JsObject, _, _>(&mut cx, "targets")? {
+        settings.targets.clear();
+        let keys = targets.get_own_property_names(&mut cx)?.to_vec(&mut cx)?;
+        for key in keys {
+            let target = key
+                .downcast_or_throw::<JsString, _>(&mut cx)?
+                .value(&mut cx);
+            let level: Handle<JsString> = targets.get(&mut cx, target.as_str())?;
+            let level = level.value(&mut cx);
+            let Some(level) = parse_level(&level) else {
+                return cx.throw_type_error(format!(
+                    "Unknown log level '{}' for target '{}'",
+                    level, target
+                ));
+            };
+            settings.targets.insert(target, level);
+        }
+    }
+    if let Some(batch_size) = parse_limit(&mut cx, options, "batchSize")?.flatten() {
+        settings.batch_size = batch_size as usize;
+    }
+    if let Some(interval) = parse_limit(&mut cx, options, "flushInterval")?.flatten() {
+        settings.flush_interval = Duration::from_millis(interval);
+    }
 
-    let disable_default_headers = obj
-        .get_opt(cx, "disableDefaultHeaders")?
-        .and_then(|v: Handle<JsValue>| v.downcast::<JsBoolean, _>(cx).ok())
-        .map(|v| v.value(cx))
-        .unwrap_or(false);
+    let mut sink = None;
+    match options.get_opt::<JsValue, _, _>(&mut cx, "file")? {
+        Some(value) if value.is_a::<JsNull, _>(&mut cx) => {
+            sink.get_or_insert(None);
+        }
+        Some(value) => {
+            let path = value
+                .downcast_or_throw::<JsString, _>(&mut cx)?
+                .value(&mut cx);
+            match LogSink::file(&path) {
+                Ok(file) => sink = Some(Some(file)),
+                Err(e) => return cx.throw_error(format!("{:#}", e)),
+            }
+        }
+        None => {}
+    }
+    match options.get_opt::<JsValue, _, _>(&mut cx, "logger")? {
+        Some(value) if value.is_a::<JsNull, _>(&mut cx) => {
+            // `{ logger: null, file }` switches to the file rather than stopping
+            sink.get_or_insert(None);
+        }
+        Some(value) => {
+            let callback = value.downcast_or_throw::<JsFunction, _>(&mut cx)?;
+            let callback = Arc::new(callback.root(&mut cx));
+            let mut channel = cx.channel();
+            // Don't keep the process alive just to deliver logs
+            channel.unref(&mut cx);
+
+            sink = Some(Some(LogSink::Callback(Box::new(
+                move |batch: Vec<LogRecord>| {
+                    let callback = callback.clone();
+                    channel.send(move |mut cx| {
+                        let cb = callback.to_inner(&mut cx);
+                        let this = cx.undefined();
+                        let records = cx.empty_array();
+                        for (i, record) in batch.into_iter().enumerate() {
+                            let record = log_record_to_js_object(&mut cx, record)?;
+                            records.set(&mut cx, i as u32, record)?;
+                        }
+                        cb.call(&mut cx, this, vec![records.upcast()])?;
+                        Ok(())
+                    });
+                },
+            ))));
+        }
+        None => {}
+    }
 
-    Ok(RequestOptions {
-        url,
-        emulation,
-        headers,
-        method,
-        body,
-        proxy,
-        timeout,
-        session_id,
-        ephemeral,
-        disable_default_headers,
-    })
+    if let Err(e) = configure_logging(settings, sink) {
+        return cx.throw_error(format!("{:#}", e));
+    }
+
+    Ok(cx.undefined())
 }
 
-// Convert Response to JS object
-fn response_to_js_object<'a, C: Context<'a>>(
+fn log_record_to_js_object<'a, C: Context<'a>>(
     cx: &mut C,
-    response: Response,
+    record: LogRecord,
 ) -> JsResult<'a, JsObject> {
     let obj = cx.empty_object();
-
-    // Status
-    let status = cx.number(response.status as f64);
-    obj.set(cx, "status", status)?;
-
-    // URL
-    let url = cx.string(&response.url);
-    obj.set(cx, "url", url)?;
-
-    // Headers
-    let headers_obj = cx.empty_object();
-    for (key, value) in response.headers {
-        let value_str = cx.string(&value);
-        headers_obj.set(cx, key.as_str(), value_str)?;
+    let timestamp = cx.number(record.timestamp as f64);
+    obj.set(cx, "timestamp", timestamp)?;
+    let level = cx.string(record.level);
+    obj.set(cx, "level", level)?;
+    let target = cx.string(record.target);
+    obj.set(cx, "target", target)?;
+    let message = cx.string(record.message);
+    obj.set(cx, "message", message)?;
+
+    let fields = cx.empty_object();
+    for (name, value) in record.fields {
+        let value = cx.string(value);
+        fields.set(cx, name.as_str(), value)?;
     }
-    obj.set(cx, "headers", headers_obj)?;
+    obj.set(cx, "fields", fields)?;
 
-    // Cookies
-    let cookies_obj = cx.empty_object();
-    for (key, value) in response.cookies {
-        let value_str = cx.string(&value);
-        cookies_obj.set(cx, key.as_str(), value_str)?;
-    }
-    obj.set(cx, "cookies", cookies_obj)?;
+    Ok(obj)
+}
 
-    // Body
-    let body = cx.string(&response.body);
-    obj.set(cx, "body", body)?;
+fn session_info_to_js_object<'a, C: Context<'a>>(
+    cx: &mut C,
+    info: SessionInfo,
+) -> JsResult<'a, JsObject> {
+    let obj = cx.empty_object();
+
+    let session_id = cx.string(info.session_id);
+    obj.set(cx, "sessionId", session_id)?;
+    let browser = cx.string(info.browser);
+    obj.set(cx, "browser", browser)?;
+    let os = cx.string(info.os);
+    obj.set(cx, "os", os)?;
+    let skip_http2 = cx.boolean(info.skip_http2);
+    obj.set(cx, "skipHttp2", skip_http2)?;
+    let skip_headers = cx.boolean(info.skip_headers);
+    obj.set(cx, "skipHeaders", skip_headers)?;
+    let proxy: Handle<JsValue> = match info.proxy {
+        Some(proxy) => cx.string(proxy).upcast(),
+        None => cx.null().upcast(),
+    };
+    obj.set(cx, "proxy", proxy)?;
+    let never_expire = cx.boolean(info.never_expire);
+    obj.set(cx, "neverExpire", never_expire)?;
+    let created_at = cx.number(info.created_at as f64);
+    obj.set(cx, "createdAt", created_at)?;
+    let last_used_at: Handle<JsValue> = match info.last_used_at {
+        Some(last_used_at) => cx.number(last_used_at as f64).upcast(),
+        None => cx.null().upcast(),
+    };
+    obj.set(cx, "lastUsedAt", last_used_at)?;
+    let request_count = cx.number(info.request_count as f64);
+    obj.set(cx, "requestCount", request_count)?;
+    let cookie_count = cx.number(info.cookie_count as f64);
+    obj.set(cx, "cookieCount", cookie_count)?;
 
     Ok(obj)
 }
 
-// Main request function exported to Node.js
-fn request(mut cx: FunctionContext) -> JsResult<JsPromise> {
-    // Get the options object
-    let options_obj = cx.argument::<JsObject>(0)?;
-
-    // Convert JS object to Rust struct
-    let options = js_object_to_request_options(&mut cx, options_obj)?;
+fn list_sessions(mut cx: FunctionContext) -> JsResult<JsArray> {
+    let js_array = cx.empty_array();
 
-    // Create a promise
-    let (deferred, promise) = cx.promise();
-    let settle_channel = cx.channel();
+    for (i, info) in list_managed_sessions().into_iter().enumerate() {
+        let obj = session_info_to_js_object(&mut cx, info)?;
+        js_array.set(&mut cx, i as u32, obj)?;
+    }
 
-    HTTP_RUNTIME.spawn(async move {
-        let result = make_request(options).await;
+    Ok(js_array)
+}
 
-        // Send result back to JS
-        deferred.settle_with(&settle_channel, move |mut cx| match result {
-            Ok(response) => response_to_js_object(&mut cx, response),
-            Err(e) => {
-                // Format error with full chain for better debugging
-                let error_msg = format!("{:#}", e);
-                cx.throw_error(error_msg)
-            }
-        });
-    });
+fn get_session_info(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let session_id = cx.argument::<JsString>(0)?.value(&mut cx);
 
-    Ok(promise)
+    match managed_session_info(&session_id) {
+        Some(info) => Ok(session_info_to_js_object(&mut cx, info)?.upcast()),
+        None => Ok(cx.null().upcast()),
+    }
 }
 
-// Get list of available browser profiles
-fn get_profiles(mut cx: FunctionContext) -> JsResult<JsArray> {
-    let js_array = cx.empty_array();
+// Connections per origin as the session saw them:
+// [{ origin, observed, observedIdle, observedInUse }]
+fn observed_connections(mut cx: FunctionContext) -> JsResult<JsArray> {
+    let session_id = cx.argument::<JsString>(0)?.value(&mut cx);
+    let origins = match observed_managed_connections(&session_id) {
+        Ok(origins) => origins,
+        Err(e) => return throw_request_error(&mut cx, e, None),
+    };
 
-    for (i, profile) in generated_profiles::BROWSER_PROFILES.iter().enumerate() {
-        let js_string = cx.string(*profile);
-        js_array.set(&mut cx, i as u32, js_string)?;
+    let js_array = cx.empty_array();
+    for (i, stats) in origins.into_iter().enumerate() {
+        let obj = cx.empty_object();
+        let origin = cx.string(stats.origin);
+        obj.set(&mut cx, "origin", origin)?;
+        let observed = cx.number(stats.observed as f64);
+        obj.set(&mut cx, "observed", observed)?;
+        let idle = cx.number(stats.observed_idle as f64);
+        obj.set(&mut cx, "observedIdle", idle)?;
+        let in_use = cx.number(stats.observed_in_use as f64);
+        obj.set(&mut cx, "observedInUse", in_use)?;
+        js_array.set(&mut cx, i as u32, obj)?;
     }
 
     Ok(js_array)
 }
 
-fn create_session(mut cx: FunctionContext) -> JsResult<JsString> {
-    let options_value = cx.argument_opt(0);
+// Serialize a session to the versioned snapshot format, with the JS-side defaults
+fn snapshot_session(mut cx: FunctionContext) -> JsResult<JsString> {
+    let session_id = cx.argument::<JsString>(0)?.value(&mut cx);
+    let defaults_obj = cx.argument::<JsObject>(1)?;
 
-    let (session_id_opt, browser_opt, proxy_opt) = if let Some(value) = options_value {
-        if value.is_a::<JsUndefined, _>(&mut cx) || value.is_a::<JsNull, _>(&mut cx) {
-            (None, None, None)
-        } else {
-            let obj = value.downcast_or_throw::<JsObject, _>(&mut cx)?;
-            let session_id = obj
-                .get_opt(&mut cx, "sessionId")?
-                .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(&mut cx).ok())
-                .map(|v| v.value(&mut cx));
-            let browser = obj
-                .get_opt(&mut cx, "browser")?
-                .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(&mut cx).ok())
-                .map(|v| v.value(&mut cx));
-            let proxy = obj
-                .get_opt(&mut cx, "proxy")?
-                .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(&mut cx).ok())
-                .map(|v| v.value(&mut cx));
-            (session_id, browser, proxy)
-        }
-    } else {
-        (None, None, None)
-    };
+    let timeout = defaults_obj
+        .get_opt(&mut cx, "timeout")?
+        .and_then(|v: Handle<JsValue>| v.downcast::<JsNumber, _>(&mut cx).ok())
+        .map(|v| v.value(&mut cx) as u64);
 
-    let session_id = session_id_opt.unwrap_or_else(generate_session_id);
-    let browser_str = browser_opt.unwrap_or_else(|| "chrome_142".to_string());
-    let emulation = parse_emulation(&browser_str);
+    let include_credentials = cx.argument::<JsBoolean>(2)?.value(&mut cx);
 
-    match create_managed_session(session_id.clone(), emulation, proxy_opt) {
-        Ok(id) => Ok(cx.string(id)),
-        Err(e) => {
-            let msg = format!("{:#}", e);
-            cx.throw_error(msg)
+    // Default headers live in the native session and are filled in from there
+    let defaults = SnapshotDefaults {
+        timeout,
+        ..SnapshotDefaults::default()
+    };
+    let json = snapshot_managed_session(&session_id, defaults).and_then(|snapshot| {
+        if include_credentials {
+            snapshot.to_json()
+        } else {
+            snapshot.without_credentials().to_json()
         }
+    });
+
+    match json {
+        Ok(json) => Ok(cx.string(json)),
+        Err(e) => throw_request_error(&mut cx, e, None),
     }
 }
 
-fn clear_session(mut cx: FunctionContext) -> JsResult<JsUndefined> {
-    let session_id = cx.argument::<JsString>(0)?.value(&mut cx);
+// Recreate a session from snapshot JSON; returns its info plus the saved defaults
+fn restore_session(mut cx: FunctionContext) -> JsResult<JsObject> {
+    let json = cx.argument::<JsString>(0)?.value(&mut cx);
+    let session_id = cx
+        .argument_opt(1)
+        .and_then(|v| v.downcast::<JsString, _>(&mut cx).ok())
+        .map(|v| v.value(&mut cx));
 
-    if let Err(e) = clear_managed_session(&session_id) {
-        let msg = format!("{:#}", e);
-        return cx.throw_error(msg);
+    let restored = SessionSnapshot::from_json(&json).and_then(|snapshot| {
+        let (session_id, proxy_pool) = restore_managed_session(&snapshot, session_id)?;
+        Ok((
+            session_id,
+            proxy_pool,
+            snapshot.proxy_settings(),
+            snapshot.defaults,
+        ))
+    });
+
+    let (session_id, proxy_pool, proxy, defaults) = match restored {
+        Ok(restored) => restored,
+        Err(e) => return throw_request_error(&mut cx, e, None),
+    };
+
+    let info = match managed_session_info(&session_id) {
+        Some(info) => info,
+        None => return throw_request_error(&mut cx, session_not_found(&session_id), None),
+    };
+
+    let obj = session_info_to_js_object(&mut cx, info)?;
+    if let Some(timeout) = defaults.timeout {
+        let timeout = cx.number(timeout as f64);
+        obj.set(&mut cx, "timeout", timeout)?;
+    }
+    if let Some(proxy_pool) = proxy_pool {
+        let proxy_pool = cx.boxed(proxy_pool);
+        obj.set(&mut cx, "proxyPool", proxy_pool)?;
+    }
+    // Report credentials and headers too, so the JS session sends the same proxy
+    if let Some(proxy) = proxy {
+        let proxy = proxy_to_js(&mut cx, &proxy)?;
+        obj.set(&mut cx, "proxy", proxy)?;
     }
 
-    Ok(cx.undefined())
+    Ok(obj)
 }
 
 fn drop_session(mut cx: FunctionContext) -> JsResult<JsUndefined> {
@@ -331,14 +1853,14 @@ fn websocket_connect(mut cx: FunctionContext) -> JsResult<JsPromise> {
     let url: Handle<JsString> = options_obj.get(&mut cx, "url")?;
     let url = url.value(&mut cx);
 
-    // Get browser (optional, defaults to chrome_142)
-    let browser_str = options_obj
+    // Get browser (optional, defaults to the latest Chrome)
+    let browser = options_obj
         .get_opt(&mut cx, "browser")?
         .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(&mut cx).ok())
-        .map(|v| v.value(&mut cx))
-        .unwrap_or_else(|| "chrome_142".to_string());
+        .map(|v| v.value(&mut cx));
 
-    let emulation = parse_emulation(&browser_str);
+    let settings = parse_emulation_settings(&mut cx, options_obj)?;
+    let emulation = parse_emulation(&mut cx, browser, settings)?;
 
     // Get headers (optional)
     let headers = if let Ok(Some(headers_value)) = options_obj.get_opt(&mut cx, "headers") {
@@ -348,10 +1870,12 @@ fn websocket_connect(mut cx: FunctionContext) -> JsResult<JsPromise> {
     };
 
     // Get proxy (optional)
-    let proxy = options_obj
-        .get_opt(&mut cx, "proxy")?
-        .and_then(|v: Handle<JsValue>| v.downcast::<JsString, _>(&mut cx).ok())
-        .map(|v| v.value(&mut cx));
+    let proxy = match options_obj.get_opt::<JsValue, _, _>(&mut cx, "proxy")? {
+        Some(value) => parse_proxy(&mut cx, value)?,
+        None => None,
+    };
+
+    let cassette = parse_cassette(&mut cx, options_obj)?.flatten();
 
     // Get callbacks
     let on_message: Handle<JsFunction> = options_obj.get(&mut cx, "onMessage")?;
@@ -359,10 +1883,11 @@ fn websocket_connect(mut cx: FunctionContext) -> JsResult<JsPromise> {
     let on_error_opt = options_obj.get_opt::<JsFunction, _, _>(&mut cx, "onError")?;
 
     let options = WebSocketOptions {
-        url,
+        url: url.clone(),
         emulation,
         headers,
         proxy,
+        cassette,
     };
 
     // Create a promise
@@ -521,10 +2046,7 @@ fn websocket_connect(mut cx: FunctionContext) -> JsResult<JsPromise> {
                 obj.set(&mut cx, "_id", id_num)?;
                 Ok(obj)
             }
-            Err(e) => {
-                let error_msg = format!("{:#}", e);
-                cx.throw_error(error_msg)
-            }
+            Err(e) => throw_request_error(&mut cx, e, Some(url)),
         });
     });
 
@@ -630,9 +2152,34 @@ fn websocket_close(mut cx: FunctionContext) -> JsResult<JsPromise> {
 #[neon::main]
 fn main(mut cx: ModuleContext) -> NeonResult<()> {
     cx.export_function("request", request)?;
+    cx.export_function("cancelRequest", cancel_native_request)?;
     cx.export_function("getProfiles", get_profiles)?;
+    cx.export_function("registerProfile", register_custom_profile)?;
+    cx.export_function("resolveProfile", resolve_profile_name)?;
+    cx.export_function("createProfileSelector", create_profile_selector)?;
+    cx.export_function("pickProfile", pick_profile)?;
+    cx.export_function("createProxyPool", create_proxy_pool)?;
+    cx.export_function("proxyPoolStatus", proxy_pool_status)?;
+    cx.export_function("createHarRecorder", create_har_recorder)?;
+    cx.export_function("harRecorderExport", har_recorder_export)?;
+    cx.export_function("harRecorderClear", har_recorder_clear)?;
+    cx.export_function("harRecorderCount", har_recorder_count)?;
+    cx.export_function("setGlobalRecorder", set_global_har_recorder)?;
+    cx.export_function("createCassette", create_cassette)?;
+    cx.export_function("cassetteExport", cassette_export)?;
+    cx.export_function("cassetteCount", cassette_count)?;
+    cx.export_function("setGlobalCassette", set_global_cassette_handle)?;
     cx.export_function("createSession", create_session)?;
-    cx.export_function("clearSession", clear_session)?;
+    cx.export_function("resetSession", reset_session)?;
+    cx.export_function("reconfigureSession", reconfigure_session)?;
+    cx.export_function("configureSessions", configure_sessions)?;
+    cx.export_function("configureLogging", configure_native_logging)?;
+    cx.export_function("configureHostLimits", configure_host_rate_limits)?;
+    cx.export_function("listSessions", list_sessions)?;
+    cx.export_function("getSessionInfo", get_session_info)?;
+    cx.export_function("observedConnections", observed_connections)?;
+    cx.export_function("snapshotSession", snapshot_session)?;
+    cx.export_function("restoreSession", restore_session)?;
     cx.export_function("dropSession", drop_session)?;
     cx.export_function("websocketConnect", websocket_connect)?;
     cx.export_function("websocketSend", websocket_send)?;
@@ -0,0 +1,267 @@
+use crate::client::HTTP_RUNTIME;
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::layer::{self, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, Registry};
+
+// Events waiting to be delivered; past it new ones are dropped and counted
+const LOG_BUFFER: usize = 10_000;
+
+static SETTINGS: Lazy<Mutex<LogSettings>> = Lazy::new(|| Mutex::new(LogSettings::default()));
+
+// Where events go while a logger or file is set
+static SENDER: Lazy<RwLock<Option<mpsc::Sender<LogRecord>>>> = Lazy::new(|| RwLock::new(None));
+
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+// Installed the first time logging is configured, with everything filtered out until then
+static FILTER: Lazy<reload::Handle<Targets, Registry>> = Lazy::new(|| {
+    let (filter, handle) = reload::Layer::new(Targets::new());
+    // Fails only if something else installed a subscriber first; its events go there
+    let _ = tracing_subscriber::registry()
+        .with(filter)
+        .with(ForwardLayer)
+        .try_init();
+    handle
+});
+
+/// Which events are kept and how they are delivered
+#[derive(Debug, Clone)]
+pub struct LogSettings {
+    /// Level for targets not in `targets`
+    pub level: LevelFilter,
+    /// Levels by target prefix, e.g. `wreq` or `wreq_js::client`
+    pub targets: IndexMap<String, LevelFilter>,
+    /// Events delivered at once at most
+    pub batch_size: usize,
+    /// How long an event may wait for its batch to fill
+    pub flush_interval: Duration,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::INFO,
+            targets: IndexMap::new(),
+            batch_size: 100,
+            flush_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// One event, as handed to JS or written to the log file
+#[derive(Debug, Clone, Serialize)]
+pub struct LogRecord {
+    /// Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    pub level: &'static str,
+    pub target: String,
+    pub message: String,
+    pub fields: IndexMap<String, String>,
+}
+
+/// Takes a batch of events; called from a background thread
+pub type LogBatchFn = dyn Fn(Vec<LogRecord>) + Send + Sync;
+
+pub enum LogSink {
+    Callback(Box<LogBatchFn>),
+    /// Appended to as JSON lines
+    File(Arc<Mutex<File>>),
+}
+
+impl LogSink {
+    pub fn file(path: &str) -> Result<Self> {
+        let file = File::options()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open log file '{}'", path))?;
+        Ok(Self::File(Arc::new(Mutex::new(file))))
+    }
+
+    async fn write(&self, batch: Vec<LogRecord>) {
+        match self {
+            Self::Callback(callback) => callback(batch),
+            Self::File(file) => {
+                let file = file.clone();
+                // File writes block, so they run off the runtime's worker threads; waiting
+                // for each keeps batches in order
+                let _ = tokio::task::spawn_blocking(move || {
+                    let mut lines = String::new();
+                    for record in &batch {
+                        if let Ok(line) = serde_json::to_string(record) {
+                            lines.push_str(&line);
+                            lines.push('\n');
+                        }
+                    }
+                    // Nowhere left to report a failed write to
+                    let _ = file.lock().unwrap().write_all(lines.as_bytes());
+                })
+                .await;
+            }
+        }
+    }
+}
+
+pub fn log_settings() -> LogSettings {
+    SETTINGS.lock().unwrap().clone()
+}
+
+/// Apply `settings`, and replace the sink when `sink` is given: `Some(None)` stops delivering
+/// events. Events already collected for the previous sink are still delivered to it.
+pub fn configure_logging(settings: LogSettings, sink: Option<Option<LogSink>>) -> Result<()> {
+    *SETTINGS.lock().unwrap() = settings.clone();
+
+    let mut sender = SENDER.write().unwrap();
+    if let Some(sink) = sink {
+        *sender = sink.map(|sink| {
+            let (tx, rx) = mpsc::channel(LOG_BUFFER);
+            HTTP_RUNTIME.spawn(deliver(rx, sink));
+            tx
+        });
+    }
+    let delivering = sender.is_some();
+    drop(sender);
+
+    // Without a sink nothing is let through, so disabled logging costs next to nothing
+    let filter = if delivering {
+        Targets::new()
+            .with_default(settings.level)
+            .with_targets(settings.targets)
+    } else {
+        Targets::new()
+    };
+    FILTER
+        .reload(filter)
+        .context("Failed to change the log filter")
+}
+
+pub fn parse_level(value: &str) -> Option<LevelFilter> {
+    match value {
+        "off" => Some(LevelFilter::OFF),
+        "error" => Some(LevelFilter::ERROR),
+        "warn" => Some(LevelFilter::WARN),
+        "info" => Some(LevelFilter::INFO),
+        "debug" => Some(LevelFilter::DEBUG),
+        "trace" => Some(LevelFilter::TRACE),
+        _ => None,
+    }
+}
+
+// Collect events into batches that are delivered when full or after the flush interval,
+// until the sink is replaced
+async fn deliver(mut rx: mpsc::Receiver<LogRecord>, sink: LogSink) {
+    while let Some(first) = rx.recv().await {
+        let (batch_size, flush_interval) = {
+            let settings = SETTINGS.lock().unwrap();
+            (settings.batch_size.max(1), settings.flush_interval)
+        };
+        let deadline = tokio::time::Instant::now() + flush_interval;
+
+        let mut batch = vec![first];
+        while batch.len() < batch_size {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(record)) => batch.push(record),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        let dropped = DROPPED.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            batch.push(LogRecord {
+                timestamp: now_millis(),
+                level: "warn",
+                target: module_path!().to_string(),
+                message: format!(
+                    "{} log events were dropped because delivery fell behind",
+                    dropped
+                ),
+                fields: IndexMap::new(),
+            });
+        }
+        sink.write(batch).await;
+    }
+}
+
+// Hands each event that passed the filter to the delivery task
+struct ForwardLayer;
+
+impl<S: Subscriber> Layer<S> for ForwardLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: layer::Context<'_, S>) {
+        let sender = SENDER.read().unwrap();
+        let Some(sender) = sender.as_ref() else {
+            return;
+        };
+
+        let metadata = event.metadata();
+        let mut visitor = RecordVisitor::default();
+        event.record(&mut visitor);
+        let record = LogRecord {
+            timestamp: now_millis(),
+            level: level_name(metadata.level()),
+            target: metadata.target().to_string(),
+            message: visitor.message,
+            fields: visitor.fields,
+        };
+        if sender.try_send(record).is_err() {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[derive(Default)]
+struct RecordVisitor {
+    message: String,
+    fields: IndexMap<String, String>,
+}
+
+impl Visit for RecordVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, format!("{:?}", value));
+    }
+}
+
+impl RecordVisitor {
+    fn insert(&mut self, field: &Field, value: String) {
+        match field.name() {
+            "message" => self.message = value,
+            name => {
+                self.fields.insert(name.to_string(), value);
+            }
+        }
+    }
+}
+
+fn level_name(level: &Level) -> &'static str {
+    match *level {
+        Level::ERROR => "error",
+        Level::WARN => "warn",
+        Level::INFO => "info",
+        Level::DEBUG => "debug",
+        Level::TRACE => "trace",
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
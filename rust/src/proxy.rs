@@ -0,0 +1,227 @@
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use wreq::header::{HeaderMap, HeaderName, HeaderValue};
+use wreq::{ClientBuilder, NoProxy, Proxy, Uri};
+
+/// Where a client's proxies come from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyMode {
+    /// Only the proxies configured here; with none, connections go direct
+    #[default]
+    Explicit,
+    /// `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY` from the environment,
+    /// and the OS proxy settings on macOS and Windows
+    System,
+}
+
+impl ProxyMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+    }
+}
+
+/// Proxies with their credentials, routing rules and the extra headers sent to them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProxySettings {
+    pub mode: ProxyMode,
+    /// Used for every scheme that has no proxy of its own
+    pub url: Option<String>,
+    pub http: Option<String>,
+    pub https: Option<String>,
+    /// Used for WebSocket connections instead of `http`/`https`
+    pub ws: Option<String>,
+    /// Hosts that bypass the proxies: domains (matching their subdomains too), IP
+    /// addresses, CIDR ranges, `localhost` or `*`
+    pub no_proxy: Vec<String>,
+    /// Credentials given apart from the URLs, so they need no percent-encoding.
+    /// They take precedence over credentials embedded in a URL.
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Sent on the CONNECT request for HTTPS targets and with plain HTTP requests
+    pub headers: IndexMap<String, String>,
+}
+
+impl ProxySettings {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: Some(url.into()),
+            ..Self::default()
+        }
+    }
+
+    /// URL to show for these settings, e.g. in session listings
+    pub fn label(&self) -> Option<String> {
+        match self.mode {
+            ProxyMode::System => Some("system".to_string()),
+            ProxyMode::Explicit => self
+                .url
+                .as_ref()
+                .or(self.https.as_ref())
+                .or(self.http.as_ref())
+                .or(self.ws.as_ref())
+                .cloned(),
+        }
+    }
+
+    /// Add these proxies to a client, or keep it off the system proxy if none are set
+    pub fn configure(&self, mut builder: ClientBuilder, websocket: bool) -> Result<ClientBuilder> {
+        if self.mode == ProxyMode::System {
+            // wreq falls back to the system proxy as long as no proxy is added
+            return Ok(builder);
+        }
+
+        // wreq uses the first proxy that intercepts a request, so scheme-specific ones go first
+        let mut proxies = Vec::new();
+        match self.ws.as_deref().filter(|_| websocket) {
+            Some(ws) => proxies.push(self.build(Proxy::all(ws))?),
+            None => {
+                if let Some(http) = &self.http {
+                    proxies.push(self.build(Proxy::http(http.as_str()))?);
+                }
+                if let Some(https) = &self.https {
+                    proxies.push(self.build(Proxy::https(https.as_str()))?);
+                }
+                if let Some(url) = &self.url {
+                    proxies.push(self.build(Proxy::all(url.as_str()))?);
+                }
+            }
+        }
+
+        if proxies.is_empty() {
+            return Ok(builder.no_proxy());
+        }
+        for proxy in proxies {
+            builder = builder.proxy(proxy);
+        }
+        Ok(builder)
+    }
+
+    fn build(&self, proxy: wreq::Result<Proxy>) -> Result<Proxy> {
+        let mut proxy = proxy
+            .context("Failed to create proxy")?
+            .no_proxy(self.bypass_list());
+
+        if let Some(username) = &self.username {
+            proxy = proxy.basic_auth(username, self.password.as_deref().unwrap_or(""));
+        }
+
+        if !self.headers.is_empty() {
+            let mut headers = HeaderMap::new();
+            for (name, value) in &self.headers {
+                let name = HeaderName::from_bytes(name.as_bytes())
+                    .with_context(|| format!("Invalid proxy header name '{}'", name))?;
+                let value = HeaderValue::from_str(value)
+                    .with_context(|| format!("Invalid value for proxy header '{}'", name))?;
+                headers.append(name, value);
+            }
+            proxy = proxy.custom_http_headers(headers);
+        }
+
+        Ok(proxy)
+    }
+
+    // `localhost` also covers the loopback addresses, which curl-style lists do not
+    fn bypass_entries(&self) -> Vec<&str> {
+        self.no_proxy
+            .iter()
+            .flat_map(|entry| match entry.trim() {
+                "localhost" => vec!["localhost", "127.0.0.0/8", "::1"],
+                entry => vec![entry],
+            })
+            .filter(|entry| !entry.is_empty())
+            .collect()
+    }
+
+    fn bypass_list(&self) -> Option<NoProxy> {
+        let entries = self.bypass_entries();
+        if entries.is_empty() {
+            return None;
+        }
+        NoProxy::from_string(&entries.join(","))
+    }
+
+    // Same rules wreq applies to the list: `*`, IP addresses and networks, or domains
+    // that also match their subdomains
+    fn bypasses(&self, host: &str) -> bool {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let ip = host.parse::<IpAddr>().ok();
+        self.bypass_entries().into_iter().any(|entry| {
+            if entry == "*" {
+                return true;
+            }
+            match ip {
+                Some(ip) => ip_matches(entry, ip),
+                None => domain_matches(entry, host),
+            }
+        })
+    }
+
+    /// URL of the proxy an HTTP request to `target` goes through, the same way the
+    /// client picks it. `None` means a direct connection, or the system proxy.
+    pub fn route(&self, target: &Uri) -> Option<String> {
+        if self.mode == ProxyMode::System {
+            return None;
+        }
+        let host = target.host()?;
+        if self.bypasses(host) {
+            return None;
+        }
+        let scheme_proxy = match target.scheme_str() {
+            Some("http") => self.http.as_ref(),
+            Some("https") => self.https.as_ref(),
+            _ => None,
+        };
+        scheme_proxy.or(self.url.as_ref()).cloned()
+    }
+}
+
+/// A proxy URL without the `user:password@` part, for errors, logs and snapshots
+pub fn without_credentials(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let authority_end = rest.find('/').unwrap_or(rest.len());
+    match rest[..authority_end].rsplit_once('@') {
+        Some((_, host)) => format!("{}://{}{}", scheme, host, &rest[authority_end..]),
+        None => url.to_string(),
+    }
+}
+
+// `example.com` and `.example.com` both match the domain itself and its subdomains
+fn domain_matches(entry: &str, host: &str) -> bool {
+    let domain = entry.trim_start_matches('.');
+    if host.eq_ignore_ascii_case(domain) {
+        return true;
+    }
+    let Some(dot) = host.len().checked_sub(domain.len() + 1) else {
+        return false;
+    };
+    host.as_bytes()[dot] == b'.'
+        && host
+            .get(dot + 1..)
+            .is_some_and(|suffix| suffix.eq_ignore_ascii_case(domain))
+}
+
+// An IP address entry, or a network such as `10.0.0.0/8`, that contains `ip`
+fn ip_matches(entry: &str, ip: IpAddr) -> bool {
+    let Some((network, prefix)) = entry.split_once('/') else {
+        return entry.parse::<IpAddr>() == Ok(ip);
+    };
+    let (Ok(network), Ok(prefix)) = (network.parse::<IpAddr>(), prefix.parse::<u32>()) else {
+        return false;
+    };
+    match (network, ip) {
+        (IpAddr::V4(network), IpAddr::V4(ip)) if prefix <= 32 => {
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            u32::from(network) & mask == u32::from(ip) & mask
+        }
+        (IpAddr::V6(network), IpAddr::V6(ip)) if prefix <= 128 => {
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            u128::from(network) & mask == u128::from(ip) & mask
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,256 @@
+use crate::selector::SplitMix64;
+use anyhow::{Context, Result};
+use moka::sync::Cache;
+use neon::prelude::Finalize;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use wreq::Proxy;
+
+// Hosts remembered by the sticky-per-host strategy
+const STICKY_CAPACITY: u64 = 10_000;
+
+/// How a pool picks the proxy for a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RotationStrategy {
+    RoundRobin,
+    Random,
+    /// Keep sending a host through the same proxy while it stays healthy
+    StickyPerHost,
+    /// Prefer the healthy proxies with the fewest recorded failures
+    LeastFailures,
+}
+
+impl RotationStrategy {
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+    }
+}
+
+/// Settings a pool is created from; also what session snapshots store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyPoolConfig {
+    pub proxies: Vec<String>,
+    pub strategy: RotationStrategy,
+    /// How long an unhealthy proxy is skipped, in milliseconds
+    pub cooldown: u64,
+    /// Consecutive failures after which a proxy is marked unhealthy
+    pub max_failures: u32,
+}
+
+impl Default for ProxyPoolConfig {
+    fn default() -> Self {
+        Self {
+            proxies: Vec::new(),
+            strategy: RotationStrategy::RoundRobin,
+            cooldown: 30_000,
+            max_failures: 1,
+        }
+    }
+}
+
+/// Health and usage of one proxy in a pool
+#[derive(Debug, Clone)]
+pub struct ProxyStatus {
+    pub proxy: String,
+    pub healthy: bool,
+    pub successes: u64,
+    pub failures: u64,
+    /// Time left before an unhealthy proxy is used again
+    pub cooldown_remaining: Option<Duration>,
+}
+
+struct PooledProxy {
+    url: String,
+    successes: AtomicU64,
+    failures: AtomicU64,
+    consecutive_failures: AtomicU32,
+    unhealthy_until: Mutex<Option<Instant>>,
+}
+
+impl PooledProxy {
+    fn new(url: String) -> Self {
+        Self {
+            url,
+            successes: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            consecutive_failures: AtomicU32::new(0),
+            unhealthy_until: Mutex::new(None),
+        }
+    }
+
+    fn cooldown_remaining(&self, now: Instant) -> Option<Duration> {
+        self.unhealthy_until
+            .lock()
+            .unwrap()
+            .filter(|until| *until > now)
+            .map(|until| until - now)
+    }
+
+    fn is_healthy(&self, now: Instant) -> bool {
+        self.cooldown_remaining(now).is_none()
+    }
+}
+
+/// A set of proxies shared by any number of sessions and requests
+pub struct ProxyPool {
+    config: ProxyPoolConfig,
+    proxies: Vec<PooledProxy>,
+    cursor: AtomicUsize,
+    rng: Mutex<SplitMix64>,
+    sticky: Cache<String, usize>,
+}
+
+impl ProxyPool {
+    pub fn new(config: ProxyPoolConfig) -> Result<Self> {
+        if config.proxies.is_empty() {
+            anyhow::bail!("Proxy pool needs at least one proxy");
+        }
+        if config.max_failures == 0 {
+            anyhow::bail!("maxFailures must be at least 1");
+        }
+        for proxy in &config.proxies {
+            Proxy::all(proxy.as_str()).with_context(|| format!("Invalid proxy '{}'", proxy))?;
+        }
+
+        let proxies = config
+            .proxies
+            .iter()
+            .cloned()
+            .map(PooledProxy::new)
+            .collect();
+        let seed = Uuid::new_v4().as_u64_pair().0;
+
+        Ok(Self {
+            config,
+            proxies,
+            cursor: AtomicUsize::new(0),
+            rng: Mutex::new(SplitMix64::new(seed)),
+            sticky: Cache::builder().max_capacity(STICKY_CAPACITY).build(),
+        })
+    }
+
+    pub fn config(&self) -> &ProxyPoolConfig {
+        &self.config
+    }
+
+    /// Pick the proxy for a request to `host`. When every proxy is cooling down,
+    /// the one that recovers first is used rather than failing the request.
+    pub fn pick(&self, host: Option<&str>) -> String {
+        let now = Instant::now();
+        let healthy: Vec<usize> = (0..self.proxies.len())
+            .filter(|&index| self.proxies[index].is_healthy(now))
+            .collect();
+
+        let index = if healthy.is_empty() {
+            self.recovering_first(now)
+        } else {
+            match self.config.strategy {
+                RotationStrategy::RoundRobin => self.next_in(&healthy),
+                RotationStrategy::Random => {
+                    let roll = self.rng.lock().unwrap().next_u64();
+                    healthy[(roll % healthy.len() as u64) as usize]
+                }
+                RotationStrategy::StickyPerHost => match host {
+                    Some(host) => self.sticky_for(host, &healthy),
+                    None => self.next_in(&healthy),
+                },
+                RotationStrategy::LeastFailures => {
+                    let fewest = healthy
+                        .iter()
+                        .map(|&index| self.proxies[index].failures.load(Ordering::Relaxed))
+                        .min()
+                        .unwrap_or(0);
+                    let least: Vec<usize> = healthy
+                        .into_iter()
+                        .filter(|&index| {
+                            self.proxies[index].failures.load(Ordering::Relaxed) == fewest
+                        })
+                        .collect();
+                    // Rotate among ties so a clean pool still spreads the load
+                    self.next_in(&least)
+                }
+            }
+        };
+
+        self.proxies[index].url.clone()
+    }
+
+    /// Record the outcome of a request sent through `proxy`
+    pub fn report(&self, proxy: &str, success: bool) {
+        let Some(entry) = self.proxies.iter().find(|entry| entry.url == proxy) else {
+            return;
+        };
+
+        if success {
+            entry.successes.fetch_add(1, Ordering::Relaxed);
+            entry.consecutive_failures.store(0, Ordering::Relaxed);
+            return;
+        }
+
+        entry.failures.fetch_add(1, Ordering::Relaxed);
+        let consecutive = entry.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if consecutive >= self.config.max_failures {
+            entry.consecutive_failures.store(0, Ordering::Relaxed);
+            let until = Instant::now() + Duration::from_millis(self.config.cooldown);
+            *entry.unhealthy_until.lock().unwrap() = Some(until);
+        }
+    }
+
+    pub fn status(&self) -> Vec<ProxyStatus> {
+        let now = Instant::now();
+        self.proxies
+            .iter()
+            .map(|entry| {
+                let cooldown_remaining = entry.cooldown_remaining(now);
+                ProxyStatus {
+                    proxy: entry.url.clone(),
+                    healthy: cooldown_remaining.is_none(),
+                    successes: entry.successes.load(Ordering::Relaxed),
+                    failures: entry.failures.load(Ordering::Relaxed),
+                    cooldown_remaining,
+                }
+            })
+            .collect()
+    }
+
+    fn next_in(&self, candidates: &[usize]) -> usize {
+        let turn = self.cursor.fetch_add(1, Ordering::Relaxed);
+        candidates[turn % candidates.len()]
+    }
+
+    fn sticky_for(&self, host: &str, healthy: &[usize]) -> usize {
+        if let Some(index) = self
+            .sticky
+            .get(host)
+            .filter(|index| healthy.contains(index))
+        {
+            return index;
+        }
+
+        let index = self.next_in(healthy);
+        self.sticky.insert(host.to_string(), index);
+        index
+    }
+
+    fn recovering_first(&self, now: Instant) -> usize {
+        (0..self.proxies.len())
+            .min_by_key(|&index| self.proxies[index].cooldown_remaining(now))
+            .unwrap_or(0)
+    }
+}
+
+impl Finalize for ProxyPool {}
+
+impl fmt::Debug for ProxyPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyPool")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
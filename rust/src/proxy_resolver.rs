@@ -0,0 +1,84 @@
+use crate::errors::{ErrorCode, Failure, Phase};
+use anyhow::{Context, Result};
+use moka::sync::Cache;
+use std::fmt;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+// Hosts whose answers are remembered at once
+const CACHE_CAPACITY: u64 = 10_000;
+
+/// Answer of the resolver: a proxy URL, or `None` to connect directly
+pub type Resolution = Result<Option<String>>;
+
+/// Asks the resolver about a URL; the answer is sent on the channel once it is known
+pub type ResolveFn = dyn Fn(String, oneshot::Sender<Resolution>) + Send + Sync;
+
+/// Picks the proxy of each request with a user-supplied function, remembering its
+/// answer per host so a host costs one call for as long as the answer is cached
+pub struct ProxyResolver {
+    resolve: Box<ResolveFn>,
+    cache: Option<Cache<String, Option<String>>>,
+}
+
+impl ProxyResolver {
+    /// Answers are kept for `cache_ttl`, or until evicted for space if it is `None`.
+    /// A zero `cache_ttl` asks the resolver about every request.
+    pub fn new(resolve: Box<ResolveFn>, cache_ttl: Option<Duration>) -> Self {
+        let cache = match cache_ttl {
+            Some(ttl) if ttl.is_zero() => None,
+            Some(ttl) => Some(
+                Cache::builder()
+                    .max_capacity(CACHE_CAPACITY)
+                    .time_to_live(ttl)
+                    .build(),
+            ),
+            None => Some(Cache::new(CACHE_CAPACITY)),
+        };
+        Self { resolve, cache }
+    }
+
+    /// Proxy for a request to `url`, or `None` to connect directly. Gives up after
+    /// `timeout`, so a resolver that never answers cannot hang the request.
+    pub async fn resolve(&self, url: &str, host: &str, timeout: Duration) -> Resolution {
+        let host = host.to_ascii_lowercase();
+        if let Some(cached) = self.cache.as_ref().and_then(|cache| cache.get(&host)) {
+            return Ok(cached);
+        }
+
+        let (tx, rx) = oneshot::channel();
+        (self.resolve)(url.to_string(), tx);
+        let proxy = tokio::time::timeout(timeout, rx)
+            .await
+            .map_err(|_| {
+                let message = format!(
+                    "Proxy resolver did not answer within the {}ms left of the request timeout",
+                    timeout.as_millis()
+                );
+                Failure::new(ErrorCode::Timeout, Phase::Proxy, message)
+            })?
+            .context(Failure::new(
+                ErrorCode::Proxy,
+                Phase::Proxy,
+                "Proxy resolver did not answer",
+            ))?
+            .context(Failure::new(
+                ErrorCode::Proxy,
+                Phase::Proxy,
+                "Proxy resolver failed",
+            ))?;
+
+        if let Some(cache) = &self.cache {
+            cache.insert(host, proxy.clone());
+        }
+        Ok(proxy)
+    }
+}
+
+impl fmt::Debug for ProxyResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyResolver")
+            .field("cached_hosts", &self.cache.as_ref().map(Cache::entry_count))
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,199 @@
+use moka::notification::RemovalCause;
+use moka::sync::Cache;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+// Limits applied to each host separately, across sessions and stateless requests
+static HOST_LIMITS: Lazy<RwLock<RateLimits>> = Lazy::new(|| RwLock::new(RateLimits::default()));
+
+// A host's limiter is dropped once no request has used it for a while; the next one
+// starts with a full bucket
+static HOST_LIMITERS: Lazy<Cache<String, Arc<Limiter>>> = Lazy::new(|| {
+    Cache::builder()
+        .max_capacity(HOST_CAPACITY)
+        .time_to_idle(HOST_IDLE)
+        .eviction_listener(
+            |host: Arc<String>, limiter: Arc<Limiter>, cause: RemovalCause| {
+                if cause.was_evicted() {
+                    let mut evicted = EVICTED_LIMITERS.lock().unwrap();
+                    evicted.retain(|_, limiter| limiter.strong_count() > 0);
+                    evicted.insert(host.as_ref().clone(), Arc::downgrade(&limiter));
+                }
+            },
+        )
+        .build()
+});
+
+// Limiters evicted while requests may still hold their slots. The host gets its limiter
+// back as long as one does, so a new one cannot let more than `maxConcurrent` through.
+static EVICTED_LIMITERS: Lazy<Mutex<HashMap<String, Weak<Limiter>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+const HOST_CAPACITY: u64 = 10_000;
+const HOST_IDLE: Duration = Duration::from_secs(600);
+
+/// Lowest request or byte rate accepted, one every 1000 seconds
+pub const MIN_RATE: f64 = 0.001;
+
+// Longest a waiter sleeps before checking its bucket again
+const MAX_SLEEP: Duration = Duration::from_secs(3600);
+
+/// Concurrency and rate limits for a session's or a host's requests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimits {
+    /// Requests in flight at once; more wait for one to finish
+    pub max_concurrent: Option<usize>,
+    /// Requests started per second, allowing bursts of one second's worth
+    pub requests_per_second: Option<f64>,
+    /// Request and response body bytes per second, allowing bursts of one second's worth
+    pub bytes_per_second: Option<f64>,
+}
+
+impl RateLimits {
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Enforces `RateLimits` on the requests that go through it
+#[derive(Debug)]
+pub struct Limiter {
+    // Tokio's semaphore is fair, so requests get their slot in the order they asked
+    concurrency: Option<Arc<Semaphore>>,
+    requests: Option<Mutex<TokenBucket>>,
+    bytes: Option<Mutex<TokenBucket>>,
+}
+
+/// A request's place among the concurrent ones, given back when dropped
+pub struct Slot {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Default for Limiter {
+    fn default() -> Self {
+        Self::new(RateLimits::default())
+    }
+}
+
+impl Limiter {
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            concurrency: limits
+                .max_concurrent
+                .map(|max| Arc::new(Semaphore::new(max.min(Semaphore::MAX_PERMITS)))),
+            requests: limits
+                .requests_per_second
+                .map(|rate| Mutex::new(TokenBucket::new(rate))),
+            bytes: limits
+                .bytes_per_second
+                .map(|rate| Mutex::new(TokenBucket::new(rate))),
+        }
+    }
+
+    /// Wait for a free slot, then for the request rate to allow one more request. Dropping
+    /// the future while it waits leaves nothing reserved.
+    pub async fn admit(&self) -> Slot {
+        let permit = match &self.concurrency {
+            Some(semaphore) => semaphore.clone().acquire_owned().await.ok(),
+            None => None,
+        };
+        if let Some(bucket) = &self.requests {
+            take(bucket, 1.0).await;
+        }
+        Slot { _permit: permit }
+    }
+
+    /// Wait until the byte rate allows `bytes` more to be transferred
+    pub async fn transfer(&self, bytes: usize) {
+        if let Some(bucket) = &self.bytes {
+            take(bucket, bytes as f64).await;
+        }
+    }
+
+    pub fn limits_bytes(&self) -> bool {
+        self.bytes.is_some()
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64) -> Self {
+        // Limits read from JS are checked; ones restored from a snapshot may not be
+        let rate = rate.max(MIN_RATE);
+        let capacity = rate.max(1.0);
+        Self {
+            rate,
+            capacity,
+            tokens: capacity,
+            updated: Instant::now(),
+        }
+    }
+
+    // Take `amount` tokens, or say how long until there are enough. Amounts over the
+    // capacity only need a full bucket and leave it in debt.
+    fn try_take(&mut self, amount: f64) -> Result<(), Duration> {
+        let now = Instant::now();
+        let refill = now.duration_since(self.updated).as_secs_f64() * self.rate;
+        self.tokens = (self.tokens + refill).min(self.capacity);
+        self.updated = now;
+
+        let needed = amount.min(self.capacity);
+        if self.tokens >= needed {
+            self.tokens -= amount;
+            Ok(())
+        } else {
+            // Long waits are slept in parts; the request's timeout ends them before long
+            let wait = (needed - self.tokens) / self.rate;
+            Err(Duration::from_secs_f64(wait.min(MAX_SLEEP.as_secs_f64())))
+        }
+    }
+}
+
+// Tokens are only taken once available, so a waiter that is cancelled costs nothing
+async fn take(bucket: &Mutex<TokenBucket>, amount: f64) {
+    loop {
+        let wait = match bucket.lock().unwrap().try_take(amount) {
+            Ok(()) => return,
+            Err(wait) => wait,
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+pub fn host_limits() -> RateLimits {
+    *HOST_LIMITS.read().unwrap()
+}
+
+/// Replace the per-host limits. Requests already waiting keep the old ones.
+pub fn configure_host_limits(limits: RateLimits) {
+    *HOST_LIMITS.write().unwrap() = limits;
+    HOST_LIMITERS.invalidate_all();
+    EVICTED_LIMITERS.lock().unwrap().clear();
+}
+
+/// The limiter shared by every request to `host`, or `None` while hosts are unlimited
+pub fn host_limiter(host: &str) -> Option<Arc<Limiter>> {
+    let limits = host_limits();
+    if limits.is_unlimited() {
+        return None;
+    }
+    let host = host.to_ascii_lowercase();
+    Some(HOST_LIMITERS.get_with(host.clone(), || {
+        let evicted = EVICTED_LIMITERS.lock().unwrap().remove(&host);
+        evicted
+            .and_then(|limiter| limiter.upgrade())
+            .unwrap_or_else(|| Arc::new(Limiter::new(limits)))
+    }))
+}
@@ -0,0 +1,115 @@
+use crate::emulation::{resolve_builtin, ProfileEntry};
+use anyhow::Result;
+use indexmap::IndexMap;
+use neon::prelude::Finalize;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Weighted random profile picker, reproducible when seeded
+pub struct ProfileSelector {
+    candidates: Vec<(String, f64)>,
+    total: f64,
+    rng: Mutex<SplitMix64>,
+}
+
+impl ProfileSelector {
+    /// Build a selector from weights keyed by profile name, `_latest` alias, family
+    /// (`safari`) or versionless name (`safari_ios`).
+    ///
+    /// A family weight is split evenly across the profiles in that family; a
+    /// profile named both directly and through its family receives both shares.
+    pub fn new(
+        profiles: &[ProfileEntry],
+        weights: &IndexMap<String, f64>,
+        seed: Option<u64>,
+    ) -> Result<Self> {
+        let mut shares: IndexMap<String, f64> = IndexMap::new();
+
+        for (key, weight) in weights {
+            if !weight.is_finite() || *weight < 0.0 {
+                anyhow::bail!("Weight for '{}' must be a non-negative number", key);
+            }
+
+            if let Some(profile) = profiles.iter().find(|p| &p.name == key) {
+                *shares.entry(profile.name.clone()).or_insert(0.0) += weight;
+                continue;
+            }
+
+            if let Some(info) = resolve_builtin(key) {
+                *shares.entry(info.name.to_string()).or_insert(0.0) += weight;
+                continue;
+            }
+
+            let mut members: Vec<&ProfileEntry> =
+                profiles.iter().filter(|p| &p.family == key).collect();
+            if members.is_empty() {
+                members = profiles.iter().filter(|p| &p.group == key).collect();
+            }
+            if members.is_empty() {
+                anyhow::bail!("'{}' is neither a known profile nor a profile family", key);
+            }
+
+            let share = weight / members.len() as f64;
+            for member in members {
+                *shares.entry(member.name.clone()).or_insert(0.0) += share;
+            }
+        }
+
+        let candidates: Vec<(String, f64)> = shares.into_iter().filter(|(_, w)| *w > 0.0).collect();
+        let total: f64 = candidates.iter().map(|(_, w)| w).sum();
+
+        if candidates.is_empty() || total <= 0.0 {
+            anyhow::bail!("Profile selector needs at least one positive weight");
+        }
+
+        let seed = seed.unwrap_or_else(|| Uuid::new_v4().as_u64_pair().0);
+
+        Ok(Self {
+            candidates,
+            total,
+            rng: Mutex::new(SplitMix64::new(seed)),
+        })
+    }
+
+    /// Pick the next profile name
+    pub fn pick(&self) -> String {
+        let roll = self.rng.lock().unwrap().next_f64() * self.total;
+
+        let mut cumulative = 0.0;
+        for (name, weight) in &self.candidates {
+            cumulative += weight;
+            if roll < cumulative {
+                return name.clone();
+            }
+        }
+
+        // Floating point rounding can leave the roll just past the last bucket
+        self.candidates[self.candidates.len() - 1].0.clone()
+    }
+}
+
+impl Finalize for ProfileSelector {}
+
+// SplitMix64: small, fast and fully deterministic for a given seed
+pub(crate) struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        // 53 random bits mapped onto [0, 1)
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
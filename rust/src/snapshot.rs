@@ -0,0 +1,162 @@
+use crate::connection_pool::PoolSettings;
+use crate::cookies::CookieRecord;
+use crate::errors::{ErrorCode, Failure, Phase};
+use crate::http_cache::CacheSettings;
+use crate::proxy::{without_credentials, ProxyMode, ProxySettings};
+use crate::proxy_pool::ProxyPoolConfig;
+use crate::rate_limit::RateLimits;
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+/// Format version written by this build. Bump it only for changes older readers
+/// would misinterpret; new optional fields don't need a bump.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// A persisted session, documented in docs/SESSION_SNAPSHOTS.md
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSnapshot {
+    pub version: u32,
+    pub session_id: String,
+    pub browser: String,
+    pub os: String,
+    #[serde(default)]
+    pub skip_http2: bool,
+    #[serde(default)]
+    pub skip_headers: bool,
+    /// Whether the session has proxy settings and where they come from. Older snapshots
+    /// leave it out and have settings only if `proxy` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_mode: Option<ProxyMode>,
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Per-scheme proxies, used instead of `proxy` for their scheme
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_http: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_https: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_ws: Option<String>,
+    /// Hosts that bypass the proxies
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_proxy: Vec<String>,
+    /// Credentials given apart from the `proxy` URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_password: Option<String>,
+    /// Extra headers sent to the proxy, e.g. on CONNECT
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub proxy_headers: IndexMap<String, String>,
+    /// Settings of the session's proxy pool, if it uses one instead of `proxy`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_pool: Option<ProxyPoolConfig>,
+    /// Connection pool limits, if they differ from the defaults
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection_pool: Option<PoolSettings>,
+    /// Concurrency and rate limits, if the session has any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limits: Option<RateLimits>,
+    /// Where the session's HTTP cache keeps responses, if it has one. Only the settings are
+    /// saved; a memory cache starts empty when restored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheSettings>,
+    #[serde(default)]
+    pub never_expire: bool,
+    /// Milliseconds since the Unix epoch
+    pub created_at: u64,
+    #[serde(default)]
+    pub defaults: SnapshotDefaults,
+    #[serde(default)]
+    pub cookies: Vec<CookieRecord>,
+}
+
+/// Request defaults kept alongside the session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotDefaults {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub headers: IndexMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct VersionProbe {
+    version: Option<u32>,
+}
+
+impl SessionSnapshot {
+    /// Drop proxy usernames, passwords and `Proxy-Authorization` headers, which snapshots
+    /// only keep when asked to
+    pub fn without_credentials(mut self) -> Self {
+        for url in [
+            &mut self.proxy,
+            &mut self.proxy_http,
+            &mut self.proxy_https,
+            &mut self.proxy_ws,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *url = without_credentials(url);
+        }
+        if let Some(pool) = &mut self.proxy_pool {
+            for url in &mut pool.proxies {
+                *url = without_credentials(url);
+            }
+        }
+        self.proxy_username = None;
+        self.proxy_password = None;
+        self.proxy_headers
+            .retain(|name, _| !name.eq_ignore_ascii_case("proxy-authorization"));
+        self
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("Failed to serialize session snapshot")
+    }
+
+    /// The session's fixed proxies with their credentials, rules and headers
+    pub fn proxy_settings(&self) -> Option<ProxySettings> {
+        if self.proxy_mode.is_none() && self.proxy.is_none() {
+            return None;
+        }
+        Some(ProxySettings {
+            mode: self.proxy_mode.unwrap_or_default(),
+            url: self.proxy.clone(),
+            http: self.proxy_http.clone(),
+            https: self.proxy_https.clone(),
+            ws: self.proxy_ws.clone(),
+            no_proxy: self.no_proxy.clone(),
+            username: self.proxy_username.clone(),
+            password: self.proxy_password.clone(),
+            headers: self.proxy_headers.clone(),
+        })
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        // Check the version first so a newer format fails with a clear message
+        // rather than whatever field happens to no longer parse
+        let probe: VersionProbe = serde_json::from_str(json)
+            .context(snapshot_failure("Session snapshot is not valid JSON"))?;
+
+        let version = probe
+            .version
+            .ok_or_else(|| snapshot_failure("Session snapshot is missing its version field"))?;
+        if version != SNAPSHOT_VERSION {
+            return Err(snapshot_failure(format!(
+                "Unsupported session snapshot version {} (this build reads version {})",
+                version, SNAPSHOT_VERSION
+            ))
+            .into());
+        }
+
+        serde_json::from_str(json).context(snapshot_failure("Malformed session snapshot"))
+    }
+}
+
+fn snapshot_failure(message: impl Into<String>) -> Failure {
+    Failure::new(ErrorCode::Snapshot, Phase::Setup, message)
+}
@@ -1,14 +1,19 @@
+use crate::cassette::{global_cassette, unexpected_send, Cassette, CassetteMode, Frame, Payload};
+use crate::dns::SystemResolver;
+use crate::emulation::EmulationProfile;
+use crate::proxy::ProxySettings;
 use anyhow::{Context, Result};
+use futures_util::stream::{self, BoxStream, SplitSink};
 use futures_util::{SinkExt, StreamExt};
 use indexmap::IndexMap;
 use neon::prelude::*;
 use once_cell::sync::Lazy;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex as StdMutex};
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex};
 use wreq::ws::message::Message;
 use wreq::ws::WebSocket;
-use wreq_util::Emulation;
 
 // Global storage for WebSocket connections
 static WS_CONNECTIONS: Lazy<StdMutex<HashMap<u64, Arc<WsConnection>>>> =
@@ -27,46 +32,81 @@ pub static WS_RUNTIME: Lazy<tokio::runtime::Runtime> = Lazy::new(|| {
 #[derive(Debug, Clone)]
 pub struct WebSocketOptions {
     pub url: String,
-    pub emulation: Emulation,
+    pub emulation: EmulationProfile,
     pub headers: IndexMap<String, String>,
-    pub proxy: Option<String>,
+    pub proxy: Option<ProxySettings>,
+    /// Records or replays the connection; the global cassette is used when `None`
+    pub cassette: Option<Arc<Cassette>>,
 }
 
+/// Messages from the server, whether a live connection or a cassette sends them
+pub type WsReceiver = BoxStream<'static, Result<Message>>;
+
 /// WebSocket connection wrapper
 pub struct WsConnection {
-    sender: Arc<Mutex<futures_util::stream::SplitSink<WebSocket, Message>>>,
+    transport: Transport,
+    recording: Option<Arc<Recording>>,
+}
+
+enum Transport {
+    Live(Mutex<SplitSink<WebSocket, Message>>),
+    Replay(Replay),
 }
 
 impl WsConnection {
-    pub fn new(sender: futures_util::stream::SplitSink<WebSocket, Message>) -> Self {
+    pub fn new(sender: SplitSink<WebSocket, Message>) -> Self {
         Self {
-            sender: Arc::new(Mutex::new(sender)),
+            transport: Transport::Live(Mutex::new(sender)),
+            recording: None,
         }
     }
 
     /// Send a text message
     pub async fn send_text(&self, text: String) -> Result<()> {
-        let mut sender = self.sender.lock().await;
-        sender
-            .send(Message::text(text))
+        self.send(Payload::Text(text))
             .await
-            .context("Failed to send text message")?;
-        Ok(())
+            .context("Failed to send text message")
     }
 
     /// Send a binary message
     pub async fn send_binary(&self, data: Vec<u8>) -> Result<()> {
-        let mut sender = self.sender.lock().await;
-        sender
-            .send(Message::binary(data))
+        self.send(Payload::Binary(data))
             .await
-            .context("Failed to send binary message")?;
+            .context("Failed to send binary message")
+    }
+
+    async fn send(&self, payload: Payload) -> Result<()> {
+        let sender = match &self.transport {
+            Transport::Live(sender) => sender,
+            Transport::Replay(replay) => return replay.send(payload),
+        };
+        let message = match &payload {
+            Payload::Text(text) => Message::text(text.clone()),
+            Payload::Binary(data) => Message::binary(data.clone()),
+        };
+        let mut sender = sender.lock().await;
+        // Kept before it goes out, so a quick reply cannot be recorded ahead of it
+        if let Some(recording) = &self.recording {
+            recording.frame(Frame::Send { data: payload });
+        }
+        sender.send(message).await?;
         Ok(())
     }
 
     /// Close the WebSocket connection
     pub async fn close(&self) -> Result<()> {
-        let mut sender = self.sender.lock().await;
+        // The server's reply to our close is not part of the conversation
+        if let Some(recording) = &self.recording {
+            recording.stop();
+        }
+        let sender = match &self.transport {
+            Transport::Live(sender) => sender,
+            Transport::Replay(replay) => {
+                replay.close();
+                return Ok(());
+            }
+        };
+        let mut sender = sender.lock().await;
         sender
             .send(Message::close(None))
             .await
@@ -75,6 +115,81 @@ impl WsConnection {
     }
 }
 
+// Where a recorded connection's messages go, until the client closes it
+struct Recording {
+    cassette: Arc<Cassette>,
+    index: usize,
+    stopped: AtomicBool,
+}
+
+impl Recording {
+    fn frame(&self, frame: Frame) {
+        if !self.stopped.load(Ordering::Relaxed) {
+            self.cassette.record_frame(self.index, frame);
+        }
+    }
+
+    fn received(&self, message: &Message) {
+        match message {
+            Message::Text(text) => self.frame(Frame::Receive {
+                data: Payload::Text(text.to_string()),
+            }),
+            Message::Binary(data) => self.frame(Frame::Receive {
+                data: Payload::Binary(data.to_vec()),
+            }),
+            Message::Close(_) => self.frame(Frame::Close),
+            _ => {}
+        }
+    }
+
+    fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+// A connection played back from a cassette. The server's messages are delivered up to the
+// next one the client sent when recording, and wait until the client sends that one.
+struct Replay {
+    frames: StdMutex<VecDeque<Frame>>,
+    messages: mpsc::UnboundedSender<Result<Message>>,
+}
+
+impl Replay {
+    fn send(&self, payload: Payload) -> Result<()> {
+        let mut frames = self.frames.lock().unwrap();
+        match frames.front() {
+            Some(Frame::Send { data }) if *data == payload => {
+                frames.pop_front();
+                self.deliver(&mut frames);
+                Ok(())
+            }
+            expected => Err(unexpected_send(expected, &payload)),
+        }
+    }
+
+    fn deliver(&self, frames: &mut VecDeque<Frame>) {
+        while let Some(frame) = frames.front() {
+            let message = match frame {
+                Frame::Send { .. } => break,
+                Frame::Receive {
+                    data: Payload::Text(text),
+                } => Message::text(text.clone()),
+                Frame::Receive {
+                    data: Payload::Binary(data),
+                } => Message::binary(data.clone()),
+                Frame::Close => Message::close(None),
+            };
+            frames.pop_front();
+            let _ = self.messages.send(Ok(message));
+        }
+    }
+
+    fn close(&self) {
+        self.frames.lock().unwrap().clear();
+        let _ = self.messages.send(Ok(Message::close(None)));
+    }
+}
+
 // Finalize implementation for proper cleanup
 impl Finalize for WsConnection {}
 
@@ -103,16 +218,28 @@ pub fn remove_connection(id: u64) {
 }
 
 /// Create WebSocket connection
-pub async fn connect_websocket(
-    options: WebSocketOptions,
-) -> Result<(WsConnection, futures_util::stream::SplitStream<WebSocket>)> {
+pub async fn connect_websocket(options: WebSocketOptions) -> Result<(WsConnection, WsReceiver)> {
+    let cassette = options.cassette.clone().or_else(global_cassette);
+    let recording = match cassette {
+        Some(cassette) if cassette.mode() == CassetteMode::Replay => {
+            return replay_websocket(&cassette, &options.url);
+        }
+        Some(cassette) => Some(Arc::new(Recording {
+            index: cassette.record_websocket(&options.url),
+            cassette,
+            stopped: AtomicBool::new(false),
+        })),
+        None => None,
+    };
+
     // Build client with emulation and proxy
-    let mut client_builder = wreq::Client::builder().emulation(options.emulation);
+    let mut client_builder = wreq::Client::builder()
+        .emulation(options.emulation.to_emulation()?)
+        .dns_resolver(SystemResolver);
 
     // Apply proxy if present
-    if let Some(proxy_url) = &options.proxy {
-        let proxy = wreq::Proxy::all(proxy_url).context("Failed to create proxy")?;
-        client_builder = client_builder.proxy(proxy);
+    if let Some(proxy) = &options.proxy {
+        client_builder = proxy.configure(client_builder, true)?;
     }
 
     // Build the client
@@ -140,7 +267,42 @@ pub async fn connect_websocket(
     // Split into sender and receiver
     let (sender, receiver) = websocket.split();
 
-    let connection = WsConnection::new(sender);
+    let mut connection = WsConnection::new(sender);
+    connection.recording = recording.clone();
+
+    let receiver = receiver
+        .map(move |message| {
+            if let (Some(recording), Ok(message)) = (&recording, &message) {
+                recording.received(message);
+            }
+            message.map_err(anyhow::Error::from)
+        })
+        .boxed();
+
+    Ok((connection, receiver))
+}
+
+// Play a recorded WebSocket back without connecting
+fn replay_websocket(cassette: &Cassette, url: &str) -> Result<(WsConnection, WsReceiver)> {
+    let mut frames = cassette.replay_websocket(url)?;
+    let (messages, receiver) = mpsc::unbounded_channel();
+    let replay = Replay {
+        frames: StdMutex::new(VecDeque::new()),
+        messages,
+    };
+    // What the server sent before the client's first message arrives right away
+    replay.deliver(&mut frames);
+    *replay.frames.lock().unwrap() = frames;
+
+    let receiver = stream::unfold(receiver, |mut receiver| async move {
+        let message = receiver.recv().await?;
+        Some((message, receiver))
+    })
+    .boxed();
+    let connection = WsConnection {
+        transport: Transport::Replay(replay),
+        recording: None,
+    };
 
     Ok((connection, receiver))
 }
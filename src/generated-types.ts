@@ -92,3 +92,46 @@ export type BrowserProfile =
   | 'okhttp_4.10'
   | 'okhttp_4.12'
   | 'okhttp_5';
+
+/**
+ * Browser families the profiles belong to
+ */
+export type BrowserFamily =
+  | 'chrome'
+  | 'edge'
+  | 'opera'
+  | 'safari'
+  | 'firefox'
+  | 'okhttp';
+
+/**
+ * Aliases resolving to the newest profile of a browser line
+ */
+export type ProfileAlias =
+  | 'chrome_latest'
+  | 'edge_latest'
+  | 'opera_latest'
+  | 'safari_ios_latest'
+  | 'safari_latest'
+  | 'safari_ipad_latest'
+  | 'firefox_latest'
+  | 'firefox_private_latest'
+  | 'firefox_android_latest'
+  | 'okhttp_latest';
+
+/**
+ * Device class a profile emulates
+ */
+export type ProfilePlatform =
+  | 'desktop'
+  | 'mobile'
+  | 'ipad';
+
+/**
+ * Rendering/network engine behind a profile
+ */
+export type BrowserEngine =
+  | 'blink'
+  | 'webkit'
+  | 'gecko'
+  | 'okhttp';
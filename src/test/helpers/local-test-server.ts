@@ -93,6 +93,28 @@ export async function startLocalTestServer(): Promise<LocalTestServer> {
       });
     }
 
+    if (path === "/connection") {
+      return json(res, { port: req.socket.remotePort });
+    }
+
+    if (path === "/redirect-to") {
+      res.statusCode = Number(url.searchParams.get("status_code") ?? "302");
+      res.setHeader("Location", url.searchParams.get("url") ?? "/get");
+      return res.end();
+    }
+
+    if (path === "/cache") {
+      // Fresh for `maxAge` seconds, then revalidated with its ETag
+      const etag = `"${url.searchParams.get("etag") ?? "v1"}"`;
+      res.setHeader("Cache-Control", `max-age=${url.searchParams.get("maxAge") ?? "0"}`);
+      res.setHeader("ETag", etag);
+      if (req.headers["if-none-match"] === etag) {
+        res.statusCode = 304;
+        return res.end();
+      }
+      return json(res, { etag, ts: Date.now() });
+    }
+
     if (path === "/user-agent") {
       return json(res, { "user-agent": req.headers["user-agent"] ?? "" });
     }
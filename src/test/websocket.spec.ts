@@ -1,7 +1,10 @@
 import assert from "node:assert";
+import { mkdtemp, rm } from "node:fs/promises";
+import { tmpdir } from "node:os";
+import { join } from "node:path";
 import { before, describe, test } from "node:test";
 import { setTimeout as sleep } from "node:timers/promises";
-import { websocket } from "../wreq-js";
+import { openCassette, websocket } from "../wreq-js";
 
 const WS_TEST_URL = process.env.WS_TEST_URL ?? "wss://echo.websocket.org";
 
@@ -142,4 +145,42 @@ describe("WebSocket", () => {
     // Close both connections
     await Promise.all([ws1.close(), ws2.close()]);
   });
+
+  test("should replay a recorded conversation from a cassette", async () => {
+    const dir = await mkdtemp(join(tmpdir(), "wreq-cassette-"));
+    const path = join(dir, "cassette.json");
+
+    try {
+      const recorded: (string | Buffer)[] = [];
+      const recording = await openCassette({ path, mode: "record" });
+      const live = await websocket({ url: WS_TEST_URL, cassette: recording, onMessage: (data) => recorded.push(data) });
+      await live.send("Hello!");
+      await sleep(200);
+      await live.close();
+      await recording.save();
+
+      const replayed: (string | Buffer)[] = [];
+      let isClosed = false;
+      const replaying = await openCassette({ path, mode: "replay" });
+      const ws = await websocket({
+        url: WS_TEST_URL,
+        cassette: replaying,
+        onMessage: (data) => replayed.push(data),
+        onClose: () => {
+          isClosed = true;
+        },
+      });
+
+      await assert.rejects(() => ws.send("Goodbye!"), /expected text "Hello!"/);
+      await ws.send("Hello!");
+      await sleep(50);
+      assert.deepStrictEqual(replayed, recorded, "The recorded messages should be received again");
+
+      await ws.close();
+      await sleep(50);
+      assert.ok(isClosed, "Should receive close event");
+    } finally {
+      await rm(dir, { recursive: true, force: true });
+    }
+  });
 });
@@ -1,26 +1,74 @@
 import { randomBytes } from "node:crypto";
+import { readFile, writeFile } from "node:fs/promises";
 import { STATUS_CODES } from "node:http";
 import type {
+  AfterResponseHook,
+  BeforeRequestHook,
+  BeforeRequestResult,
   BodyInit,
-  BrowserProfile,
+  CacheStatus,
+  CassetteHandle,
+  CassetteMode,
+  CassetteOptions,
+  ConnectionPoolOptions,
   CookieMode,
   CreateSessionOptions,
+  CustomProfileDefinition,
+  EmulationOS,
+  Har,
+  HarRecorderHandle,
+  HarRecorderOptions,
   HeadersInit,
   HeaderTuple,
+  HookRequest,
+  HookResponse,
+  HookResponseInit,
+  HttpCacheOptions,
+  LoggingOptions,
+  LogRecord,
   NativeResponse,
   NativeWebSocketConnection,
+  ObservedConnections,
+  ProfileInfo,
+  ProfileName,
+  ProfileQuery,
+  ProfileSelectorHandle,
+  ProfileSelectorOptions,
+  ProxyInit,
+  ProxyOptions,
+  ProxyPoolHandle,
+  ProxyPoolOptions,
+  ProxyResolver,
+  ProxyStatus,
+  RateLimitOptions,
+  ReconfigureSessionOptions,
+  RequestErrorCode,
+  RequestErrorDetails,
+  RequestErrorPhase,
   RequestOptions,
+  RestoreSessionOptions,
   SessionHandle,
+  SessionInfo,
+  SessionManagerOptions,
+  SessionResetMode,
+  SnapshotOptions,
   WebSocketOptions,
   RequestInit as WreqRequestInit,
 } from "./types";
-import { RequestError } from "./types";
+import { ProxyConnectError, RequestError, UnknownProfileError } from "./types";
+
+// Proxy as the native module reads it: a URL, or the proxy options with header tuples
+type NativeProxy = string | (Omit<ProxyOptions, "headers"> & { headers?: HeaderTuple[] });
 
 interface NativeWebSocketOptions {
   url: string;
-  browser: BrowserProfile;
+  browser?: ProfileName;
+  os?: EmulationOS;
+  skipHttp2?: boolean;
+  skipHeaders?: boolean;
   headers: Record<string, string> | HeaderTuple[];
-  proxy?: string;
+  proxy?: NativeProxy;
+  cassette?: NativeCassette;
   onMessage: (data: string | Buffer) => void;
   onClose?: () => void;
   onError?: (error: string) => void;
@@ -28,22 +76,138 @@ interface NativeWebSocketOptions {
 
 interface NativeSessionOptions {
   sessionId: string;
-  browser: BrowserProfile;
-  proxy?: string;
+  browser: ProfileName;
+  os?: EmulationOS;
+  skipHttp2?: boolean;
+  skipHeaders?: boolean;
+  proxy?: NativeProxy;
+  proxyPool?: NativeProxyPool;
+  proxyResolver?: NativeProxyResolver;
+  proxyResolverCacheTtl?: number | null;
+  connectionPool?: ConnectionPoolOptions;
+  rateLimits?: RateLimitOptions;
+  cache?: HttpCacheOptions;
+  recorder?: NativeHarRecorder;
+  cassette?: NativeCassette;
+  beforeRequest?: NativeBeforeRequestHook;
+  afterResponse?: NativeAfterResponseHook;
+  neverExpire?: boolean;
+  headers?: HeaderTuple[];
+}
+
+type NativeRestoredSession = Omit<SessionInfo, "proxy"> & {
+  proxy: NativeProxy | null;
+  timeout?: number;
+  proxyPool?: NativeProxyPool;
+};
+
+interface NativeReconfigureOptions {
+  browser?: ProfileName;
+  os?: EmulationOS;
+  skipHttp2?: boolean;
+  skipHeaders?: boolean;
+  proxy?: NativeProxy | null;
+  proxyPool?: NativeProxyPool | null;
+  proxyResolver?: NativeProxyResolver | null;
+  proxyResolverCacheTtl?: number | null;
+  connectionPool?: ConnectionPoolOptions;
+  rateLimits?: RateLimitOptions | null;
+  cache?: HttpCacheOptions | null;
+  recorder?: NativeHarRecorder | null;
+  cassette?: NativeCassette | null;
+  beforeRequest?: NativeBeforeRequestHook | null;
+  afterResponse?: NativeAfterResponseHook | null;
 }
 
+// Called by the native module for each uncached host; `done` must be called exactly once
+type NativeProxyResolver = (url: string, done: (error: string | null, proxy: string | null) => void) => void;
+
+type NativeHookRequest = Omit<HookRequest, "headers"> & { headers: HeaderTuple[] };
+type NativeHookResponse = Omit<HookResponseInit, "headers"> & { headers?: HeaderTuple[] };
+
+// Called by the native module before each session request; `done` must be called exactly once, with the
+// request to send or a response to answer with
+type NativeBeforeRequestHook = (
+  request: HookRequest,
+  done: (error: string | null, request: NativeHookRequest | null, response: NativeHookResponse | null) => void,
+) => void;
+
+// Called by the native module with each session response; `done` must be called exactly once
+type NativeAfterResponseHook = (
+  request: HookRequest,
+  response: NativeResponse,
+  done: (error: string | null, response: NativeHookResponse | null) => void,
+) => void;
+
+type NativeRequestOptions = Omit<RequestOptions, "proxy"> & {
+  proxy?: NativeProxy;
+  proxyPool?: NativeProxyPool;
+  // Set when the request can be aborted, so the native side can be told to stop it
+  requestId?: string;
+};
+
 let nativeBinding: {
-  request: (options: RequestOptions) => Promise<NativeResponse>;
-  getProfiles: () => string[];
+  request: (options: NativeRequestOptions) => Promise<NativeResponse>;
+  cancelRequest: (requestId: string) => void;
+  getProfiles: () => ProfileInfo[];
+  registerProfile: (name: string, definition: string) => void;
+  resolveProfile: (name?: string) => string;
+  createProfileSelector: (options: ProfileSelectorOptions) => NativeProfileSelector;
+  pickProfile: (selector: NativeProfileSelector) => string;
+  createProxyPool: (options: ProxyPoolOptions) => NativeProxyPool;
+  proxyPoolStatus: (pool: NativeProxyPool) => ProxyStatus[];
+  createHarRecorder: (options?: HarRecorderOptions) => NativeHarRecorder;
+  harRecorderExport: (recorder: NativeHarRecorder) => string;
+  harRecorderClear: (recorder: NativeHarRecorder) => void;
+  harRecorderCount: (recorder: NativeHarRecorder) => number;
+  setGlobalRecorder: (recorder: NativeHarRecorder | null) => void;
+  createCassette: (options: NativeCassetteOptions) => NativeCassette;
+  cassetteExport: (cassette: NativeCassette) => string;
+  cassetteCount: (cassette: NativeCassette) => number;
+  setGlobalCassette: (cassette: NativeCassette | null) => void;
   websocketConnect: (options: NativeWebSocketOptions) => Promise<NativeWebSocketConnection>;
   websocketSend: (ws: NativeWebSocketConnection, data: string | Buffer) => Promise<void>;
   websocketClose: (ws: NativeWebSocketConnection) => Promise<void>;
   createSession: (options: NativeSessionOptions) => string;
-  clearSession: (sessionId: string) => void;
+  resetSession: (sessionId: string, mode: SessionResetMode) => void;
+  reconfigureSession: (sessionId: string, options: NativeReconfigureOptions) => void;
+  configureSessions: (options: SessionManagerOptions) => void;
+  configureLogging: (options: LoggingOptions) => void;
+  configureHostLimits: (limits: RateLimitOptions | null) => void;
+  listSessions: () => SessionInfo[];
+  getSessionInfo: (sessionId: string) => SessionInfo | null;
+  observedConnections: (sessionId: string) => ObservedConnections[];
+  snapshotSession: (sessionId: string, defaults: { timeout?: number }, includeCredentials: boolean) => string;
+  restoreSession: (snapshot: string, sessionId?: string) => NativeRestoredSession;
   dropSession: (sessionId: string) => void;
 };
 
-let cachedProfiles: BrowserProfile[] | undefined;
+// Opaque handle to a native ProfileSelector
+interface NativeProfileSelector {
+  readonly __brand: "NativeProfileSelector";
+}
+
+// Opaque handle to a native ProxyPool
+interface NativeProxyPool {
+  readonly __brand: "NativeProxyPool";
+}
+
+// Opaque handle to a native HAR recorder
+interface NativeHarRecorder {
+  readonly __brand: "NativeHarRecorder";
+}
+
+// Opaque handle to a native cassette
+interface NativeCassette {
+  readonly __brand: "NativeCassette";
+}
+
+// The file's contents go to the native side instead of its path; replaying needs them
+type NativeCassetteOptions = Omit<CassetteOptions, "path"> & { contents?: string };
+
+let cachedProfiles: ProfileInfo[] | undefined;
+let nextRequestId = 0;
+let cachedProfileNames: ProfileName[] | undefined;
 
 function loadNativeBinding() {
   const platform = process.platform;
@@ -102,18 +266,20 @@ const websocketFinalizer =
       })
     : undefined;
 
-const DEFAULT_BROWSER: BrowserProfile = "chrome_142";
-
 type SessionDefaults = {
-  browser: BrowserProfile;
-  proxy?: string;
+  browser: ProfileName;
+  os?: EmulationOS;
+  skipHttp2?: boolean;
+  skipHeaders?: boolean;
+  proxy?: ProxyInit;
+  proxyPool?: ProxyPool;
   timeout?: number;
 };
 
 type SessionResolution = {
-  sessionId: string;
+  // Omitted for ephemeral requests, which go through a shared cookieless client
+  sessionId?: string;
   cookieMode: CookieMode;
-  dropAfterRequest: boolean;
 };
 
 function generateSessionId(): string {
@@ -125,16 +291,152 @@ function generateSessionId(): string {
   return randomBytes(16).toString("hex");
 }
 
+function toNativeProxy(proxy: ProxyInit): NativeProxy {
+  if (typeof proxy === "string") {
+    return proxy;
+  }
+
+  if (!proxy || typeof proxy !== "object") {
+    throw new RequestError("proxy must be a URL string or a proxy options object");
+  }
+
+  for (const key of ["url", "http", "https", "ws"] as const) {
+    const url = proxy[key];
+    if (url !== undefined && (typeof url !== "string" || url.length === 0)) {
+      throw new RequestError(`proxy.${key} must be a non-empty URL string`);
+    }
+  }
+
+  if (proxy.mode !== undefined && proxy.mode !== "explicit" && proxy.mode !== "system") {
+    throw new RequestError('proxy.mode must be "explicit" or "system"');
+  }
+
+  const noProxy = proxy.noProxy;
+  if (noProxy !== undefined && !(Array.isArray(noProxy) && noProxy.every((entry) => typeof entry === "string"))) {
+    throw new RequestError("proxy.noProxy must be an array of strings");
+  }
+
+  const headerTuples = proxy.headers !== undefined ? new Headers(proxy.headers).toTuples() : [];
+
+  return {
+    ...(proxy.mode === "system" && { mode: proxy.mode }),
+    ...(proxy.url !== undefined && { url: proxy.url }),
+    ...(proxy.http !== undefined && { http: proxy.http }),
+    ...(proxy.https !== undefined && { https: proxy.https }),
+    ...(proxy.ws !== undefined && { ws: proxy.ws }),
+    ...(noProxy !== undefined && noProxy.length > 0 && { noProxy: [...noProxy] }),
+    ...(proxy.username !== undefined && { username: proxy.username }),
+    ...(proxy.password !== undefined && { password: proxy.password }),
+    ...(headerTuples.length > 0 && { headers: headerTuples }),
+  };
+}
+
+function toNativeProxyResolver(resolver: ProxyResolver): NativeProxyResolver {
+  if (typeof resolver !== "function") {
+    throw new RequestError("proxyResolver must be a function");
+  }
+
+  return (url, done) => {
+    Promise.resolve()
+      .then(() => resolver(url))
+      .then(
+        (proxy) => {
+          if (proxy === null || proxy === undefined) {
+            done(null, null);
+          } else if (typeof proxy === "string" && proxy.length > 0) {
+            done(null, proxy);
+          } else {
+            done("proxyResolver must return a proxy URL or null", null);
+          }
+        },
+        (error: unknown) => done(String(error), null),
+      );
+  };
+}
+
+function toNativeBeforeRequestHook(hook: BeforeRequestHook): NativeBeforeRequestHook {
+  if (typeof hook !== "function") {
+    throw new RequestError("beforeRequest must be a function");
+  }
+
+  return (request, done) => {
+    Promise.resolve()
+      .then(() => hook(request))
+      .then((result: BeforeRequestResult) => {
+        if (result !== undefined && "response" in result) {
+          done(null, null, toNativeHookResponse(result.response));
+          return;
+        }
+
+        const next = result ?? request;
+        done(null, { ...next, headers: new Headers(next.headers).toTuples() }, null);
+      })
+      .catch((error: unknown) => done(String(error), null, null));
+  };
+}
+
+function toNativeAfterResponseHook(hook: AfterResponseHook): NativeAfterResponseHook {
+  if (typeof hook !== "function") {
+    throw new RequestError("afterResponse must be a function");
+  }
+
+  return (request, response, done) => {
+    Promise.resolve()
+      .then(() => hook(response, request))
+      .then((result: HookResponse | undefined) => done(null, toNativeHookResponse(result ?? response)))
+      .catch((error: unknown) => done(String(error), null));
+  };
+}
+
+function toNativeHookResponse(response: HookResponseInit): NativeHookResponse {
+  return {
+    ...(response.status !== undefined && { status: response.status }),
+    ...(response.url !== undefined && { url: response.url }),
+    ...(response.headers !== undefined && { headers: new Headers(response.headers).toTuples() }),
+    ...(response.cookies !== undefined && { cookies: response.cookies }),
+    ...(response.body !== undefined && { body: response.body }),
+  };
+}
+
+// Two proxy settings are the same session proxy when they normalize to the same native value
+function sameProxy(a?: ProxyInit, b?: ProxyInit): boolean {
+  const key = (proxy?: ProxyInit) => (proxy === undefined ? null : JSON.stringify(toNativeProxy(proxy)));
+  return key(a) === key(b);
+}
+
 function normalizeSessionOptions(options?: CreateSessionOptions): { sessionId: string; defaults: SessionDefaults } {
   const sessionId = options?.sessionId ?? generateSessionId();
   const defaults: SessionDefaults = {
-    browser: options?.browser ?? DEFAULT_BROWSER,
+    browser: options?.browser ?? options?.profileSelector?.pick() ?? resolveProfile(),
   };
 
+  if (options?.os !== undefined) {
+    defaults.os = options.os;
+  }
+
+  if (options?.skipHttp2 !== undefined) {
+    defaults.skipHttp2 = options.skipHttp2;
+  }
+
+  if (options?.skipHeaders !== undefined) {
+    defaults.skipHeaders = options.skipHeaders;
+  }
+
   if (options?.proxy !== undefined) {
     defaults.proxy = options.proxy;
   }
 
+  if (options?.proxyPool !== undefined) {
+    if (options.proxy !== undefined) {
+      throw new RequestError("Provide either `proxy` or `proxyPool`, not both.");
+    }
+    defaults.proxyPool = toProxyPool(options.proxyPool);
+  }
+
+  if (options?.proxyResolver !== undefined && (options.proxy !== undefined || options.proxyPool !== undefined)) {
+    throw new RequestError("`proxyResolver` cannot be combined with `proxy` or `proxyPool`.");
+  }
+
   if (options?.timeout !== undefined) {
     defaults.timeout = options.timeout;
   }
@@ -325,6 +627,8 @@ function cloneNativeResponse(payload: NativeResponse): NativeResponse {
     body: payload.body,
     cookies: { ...payload.cookies },
     url: payload.url,
+    proxy: payload.proxy,
+    ...(payload.cacheStatus !== undefined && { cacheStatus: payload.cacheStatus }),
   };
 }
 
@@ -338,6 +642,16 @@ export class Response {
   readonly type: ResponseType = "basic";
   readonly cookies: Record<string, string>;
   readonly body: string;
+  /**
+   * Proxy the request was sent through, or `null` for a direct connection, a host matched by
+   * `noProxy`, or the system proxy settings. With a proxy pool this is the proxy the pool picked.
+   */
+  readonly proxy: string | null;
+  /**
+   * Whether the session's HTTP cache answered: `hit`, `miss` or `revalidated`. `null` when the session has
+   * no cache, or the request bypassed it (not a GET, `cache: "no-store"`, or its own validator headers).
+   */
+  readonly cacheStatus: CacheStatus | null;
   bodyUsed = false;
 
   private readonly payload: NativeResponse;
@@ -354,6 +668,8 @@ export class Response {
     this.redirected = this.url !== requestUrl;
     this.cookies = { ...payload.cookies };
     this.body = payload.body;
+    this.proxy = payload.proxy ?? null;
+    this.cacheStatus = payload.cacheStatus ?? null;
   }
 
   async json<T = unknown>(): Promise<T> {
@@ -385,7 +701,7 @@ export class Response {
 export class Session implements SessionHandle {
   readonly id: string;
   private disposed = false;
-  private readonly defaults: SessionDefaults;
+  private defaults: SessionDefaults;
 
   constructor(id: string, defaults: SessionDefaults) {
     this.id = id;
@@ -396,13 +712,20 @@ export class Session implements SessionHandle {
     return this.disposed;
   }
 
+  /**
+   * Browser profile the session is bound to.
+   */
+  get browser(): ProfileName {
+    return this.defaults.browser;
+  }
+
   private ensureActive(): void {
     if (this.disposed) {
       throw new RequestError("Session has been closed");
     }
   }
 
-  private enforceBrowser(browser?: BrowserProfile): BrowserProfile {
+  private enforceBrowser(browser?: ProfileName): ProfileName {
     const resolved = browser ?? this.defaults.browser;
 
     if (resolved !== this.defaults.browser) {
@@ -412,18 +735,52 @@ export class Session implements SessionHandle {
     return resolved;
   }
 
-  private enforceProxy(proxy?: string): string | undefined {
+  private enforceEmulationSettings(config: WreqRequestInit): void {
+    if (config.os !== undefined && config.os !== (this.defaults.os ?? "macos")) {
+      throw new RequestError("Session os cannot be changed after creation");
+    }
+
+    if (config.skipHttp2 !== undefined && config.skipHttp2 !== (this.defaults.skipHttp2 ?? false)) {
+      throw new RequestError("Session skipHttp2 cannot be changed after creation");
+    }
+
+    if (config.skipHeaders !== undefined && config.skipHeaders !== (this.defaults.skipHeaders ?? false)) {
+      throw new RequestError("Session skipHeaders cannot be changed after creation");
+    }
+
+    if (this.defaults.os !== undefined) {
+      config.os = this.defaults.os;
+    }
+
+    if (this.defaults.skipHttp2 !== undefined) {
+      config.skipHttp2 = this.defaults.skipHttp2;
+    }
+
+    if (this.defaults.skipHeaders !== undefined) {
+      config.skipHeaders = this.defaults.skipHeaders;
+    }
+  }
+
+  private enforceProxy(proxy?: ProxyInit): ProxyInit | undefined {
     if (proxy === undefined) {
       return this.defaults.proxy;
     }
 
-    if ((this.defaults.proxy ?? null) !== (proxy ?? null)) {
+    if (!sameProxy(this.defaults.proxy, proxy)) {
       throw new RequestError("Session proxy cannot be changed after creation");
     }
 
     return proxy;
   }
 
+  private enforceProxyPool(proxyPool?: ProxyPoolHandle): ProxyPool | undefined {
+    if (proxyPool !== undefined && proxyPool !== this.defaults.proxyPool) {
+      throw new RequestError("Session proxy pool cannot be changed after creation");
+    }
+
+    return this.defaults.proxyPool;
+  }
+
   async fetch(input: string | URL, init?: WreqRequestInit): Promise<Response> {
     this.ensureActive();
 
@@ -434,6 +791,7 @@ export class Session implements SessionHandle {
     };
 
     config.browser = this.enforceBrowser(config.browser);
+    this.enforceEmulationSettings(config);
 
     const proxy = this.enforceProxy(config.proxy);
     if (proxy !== undefined || config.proxy !== undefined) {
@@ -444,6 +802,11 @@ export class Session implements SessionHandle {
       }
     }
 
+    const proxyPool = this.enforceProxyPool(config.proxyPool);
+    if (proxyPool !== undefined) {
+      config.proxyPool = proxyPool;
+    }
+
     if (config.timeout === undefined && this.defaults.timeout !== undefined) {
       config.timeout = this.defaults.timeout;
     }
@@ -451,15 +814,174 @@ export class Session implements SessionHandle {
     return fetch(input, config);
   }
 
+  /**
+   * Empty the session's cookie jar. Pooled connections stay open; same as `reset("cookies")`.
+   */
   async clearCookies(): Promise<void> {
+    await this.reset("cookies");
+  }
+
+  /**
+   * Throw away part of the session's state; the profile, proxy and default headers stay.
+   * - `cookies`: empty the cookie jar, keeping warm connections
+   * - `connections`: close pooled connections so the next requests make new TLS handshakes, keeping the cookies
+   * - `all` (default): both
+   */
+  async reset(mode: SessionResetMode = "all"): Promise<void> {
     this.ensureActive();
+
+    if (mode !== "cookies" && mode !== "connections" && mode !== "all") {
+      throw new RequestError('reset mode must be "cookies", "connections" or "all"');
+    }
+
     try {
-      nativeBinding.clearSession(this.id);
+      nativeBinding.resetSession(this.id, mode);
     } catch (error) {
-      throw new RequestError(String(error));
+      throw toRequestError(error);
+    }
+  }
+
+  /**
+   * Current configuration and usage of the session, or `null` once it has been closed or evicted.
+   */
+  info(): SessionInfo | null {
+    return getSessionInfo(this.id);
+  }
+
+  /**
+   * Connections per origin as this session saw them; see {@link observedConnections}.
+   */
+  observedConnections(): ObservedConnections[] {
+    this.ensureActive();
+    return observedConnections(this.id);
+  }
+
+  /**
+   * Change the session's profile, proxy, proxy pool, proxy resolver and/or connection pool limits in place.
+   * Cookies carry over to the new configuration; pooled connections do not.
+   */
+  async reconfigure(options: ReconfigureSessionOptions): Promise<void> {
+    this.ensureActive();
+
+    const next: SessionDefaults = { ...this.defaults };
+    const emulationChanged =
+      options.browser !== undefined ||
+      options.os !== undefined ||
+      options.skipHttp2 !== undefined ||
+      options.skipHeaders !== undefined;
+
+    if (options.browser !== undefined) {
+      validateBrowserProfile(options.browser);
+      next.browser = options.browser;
+    }
+
+    if (options.os !== undefined) {
+      next.os = options.os;
+    }
+
+    if (options.skipHttp2 !== undefined) {
+      next.skipHttp2 = options.skipHttp2;
+    }
+
+    if (options.skipHeaders !== undefined) {
+      next.skipHeaders = options.skipHeaders;
+    }
+
+    if (options.proxy !== undefined && options.proxyPool !== undefined && options.proxyPool !== null) {
+      throw new RequestError("Provide either `proxy` or `proxyPool`, not both.");
+    }
+
+    if (options.proxyResolver && (options.proxy || options.proxyPool)) {
+      throw new RequestError("`proxyResolver` cannot be combined with `proxy` or `proxyPool`.");
+    }
+
+    const proxyPool = options.proxyPool ? toProxyPool(options.proxyPool) : options.proxyPool;
+    const proxy = options.proxy ? toNativeProxy(options.proxy) : options.proxy;
+    const proxyResolver = options.proxyResolver ? toNativeProxyResolver(options.proxyResolver) : options.proxyResolver;
+
+    if (options.proxy === null) {
+      delete next.proxy;
+    } else if (options.proxy !== undefined) {
+      next.proxy = options.proxy;
+      delete next.proxyPool;
+    }
+
+    if (proxyPool === null) {
+      delete next.proxyPool;
+    } else if (proxyPool !== undefined) {
+      next.proxyPool = proxyPool;
+      delete next.proxy;
+    }
+
+    if (proxyResolver) {
+      delete next.proxy;
+      delete next.proxyPool;
+    }
+
+    try {
+      nativeBinding.reconfigureSession(this.id, {
+        ...(emulationChanged && {
+          browser: next.browser,
+          ...(next.os !== undefined && { os: next.os }),
+          ...(next.skipHttp2 !== undefined && { skipHttp2: next.skipHttp2 }),
+          ...(next.skipHeaders !== undefined && { skipHeaders: next.skipHeaders }),
+        }),
+        ...(proxy !== undefined && { proxy }),
+        ...(proxyPool !== undefined && { proxyPool: proxyPool === null ? null : proxyPool.nativeHandle }),
+        ...(proxyResolver !== undefined && { proxyResolver }),
+        ...(options.proxyResolverCacheTtl !== undefined && { proxyResolverCacheTtl: options.proxyResolverCacheTtl }),
+        ...(options.connectionPool !== undefined && { connectionPool: options.connectionPool }),
+        ...(options.rateLimits !== undefined && { rateLimits: options.rateLimits }),
+        ...(options.cache !== undefined && { cache: options.cache }),
+        ...(options.recorder !== undefined && {
+          recorder: options.recorder === null ? null : toHarRecorder(options.recorder).nativeHandle,
+        }),
+        ...(options.cassette !== undefined && {
+          cassette: options.cassette === null ? null : toCassette(options.cassette).nativeHandle,
+        }),
+        ...(options.beforeRequest !== undefined && {
+          beforeRequest: options.beforeRequest === null ? null : toNativeBeforeRequestHook(options.beforeRequest),
+        }),
+        ...(options.afterResponse !== undefined && {
+          afterResponse: options.afterResponse === null ? null : toNativeAfterResponseHook(options.afterResponse),
+        }),
+      });
+    } catch (error) {
+      throw toRequestError(error);
+    }
+
+    this.defaults = next;
+  }
+
+  /**
+   * Serialize the session's profile, proxy, defaults and full cookie jar to the versioned
+   * snapshot format (JSON). Restore it with {@link restoreSession}, in this or another process.
+   * A proxy resolver is not saved; pass it to `reconfigure()` again after restoring.
+   * Proxy credentials are left out unless `includeCredentials` is set.
+   */
+  snapshot(options?: SnapshotOptions): string {
+    this.ensureActive();
+
+    try {
+      return nativeBinding.snapshotSession(
+        this.id,
+        { ...(this.defaults.timeout !== undefined && { timeout: this.defaults.timeout }) },
+        options?.includeCredentials ?? false,
+      );
+    } catch (error) {
+      throw toRequestError(error);
     }
   }
 
+  /**
+   * Write {@link snapshot} to a file. Load it back with {@link loadSession}.
+   * The file contains the session's cookies, and proxy credentials if `includeCredentials`
+   * is set, in plain text.
+   */
+  async save(path: string, options?: SnapshotOptions): Promise<void> {
+    await writeFile(path, this.snapshot(options), { encoding: "utf8", mode: 0o600 });
+  }
+
   async close(): Promise<void> {
     if (this.disposed) {
       return;
@@ -496,7 +1018,6 @@ function resolveSessionContext(config: WreqRequestInit): SessionResolution {
     return {
       sessionId: sessionCandidate.id,
       cookieMode: "session",
-      dropAfterRequest: false,
     };
   }
 
@@ -512,7 +1033,6 @@ function resolveSessionContext(config: WreqRequestInit): SessionResolution {
     return {
       sessionId: providedSessionId,
       cookieMode: "session",
-      dropAfterRequest: false,
     };
   }
 
@@ -520,11 +1040,7 @@ function resolveSessionContext(config: WreqRequestInit): SessionResolution {
     throw new RequestError("cookieMode 'session' requires a session or sessionId");
   }
 
-  return {
-    sessionId: generateSessionId(),
-    cookieMode: "ephemeral",
-    dropAfterRequest: true,
-  };
+  return { cookieMode: "ephemeral" };
 }
 
 interface AbortHandler {
@@ -557,7 +1073,7 @@ function isAbortError(error: unknown): error is Error {
   return Boolean(error) && typeof (error as Error).name === "string" && (error as Error).name === "AbortError";
 }
 
-function setupAbort(signal?: AbortSignal | null): AbortHandler | null {
+function setupAbort(signal?: AbortSignal | null, onAborted?: () => void): AbortHandler | null {
   if (!signal) {
     return null;
   }
@@ -570,6 +1086,7 @@ function setupAbort(signal?: AbortSignal | null): AbortHandler | null {
 
   const promise = new Promise<never>((_, reject) => {
     onAbort = () => {
+      onAborted?.();
       reject(createAbortError(signal.reason));
     };
 
@@ -660,25 +1177,60 @@ function ensureBodyAllowed(method: string, body?: string): void {
   }
 }
 
-function validateBrowserProfile(browser?: BrowserProfile): void {
+function validateBrowserProfile(browser?: ProfileName): void {
   if (!browser) {
     return;
   }
 
-  const profiles = getProfiles();
+  resolveProfile(browser);
+}
+
+type NativeRequestError = Error & {
+  code?: RequestErrorCode;
+  phase?: RequestErrorPhase;
+  retryable?: boolean;
+  url?: string;
+  proxy?: string;
+};
+
+// Keep the code, phase and retryability the native layer classified the failure with
+function toRequestError(error: unknown): RequestError {
+  if (error instanceof RequestError) {
+    return error;
+  }
+
+  const nativeError = error as NativeRequestError;
+  if (nativeError?.code === undefined || nativeError.phase === undefined) {
+    return new RequestError(String(error));
+  }
 
-  if (!profiles.includes(browser)) {
-    throw new RequestError(`Invalid browser profile: ${browser}. Available profiles: ${profiles.join(", ")}`);
+  const details: RequestErrorDetails = {
+    code: nativeError.code,
+    phase: nativeError.phase,
+    retryable: nativeError.retryable === true,
+    ...(nativeError.url !== undefined && { url: nativeError.url }),
+    ...(nativeError.proxy !== undefined && { proxy: nativeError.proxy }),
+  };
+  if (nativeError.code === "ERR_PROXY_CONNECT") {
+    return new ProxyConnectError(nativeError.message, details);
   }
+
+  return new RequestError(nativeError.message, details);
 }
 
 async function dispatchRequest(
-  options: RequestOptions,
+  options: NativeRequestOptions,
   requestUrl: string,
   signal?: AbortSignal | null,
 ): Promise<Response> {
-  const abortHandler = setupAbort(signal);
-  const nativePromise = nativeBinding.request(options);
+  // Aborting also stops the native request, so it gives up its place in any rate limit queue
+  const requestId = signal ? String(++nextRequestId) : undefined;
+  const abortHandler = setupAbort(signal, () => {
+    if (requestId !== undefined) {
+      nativeBinding.cancelRequest(requestId);
+    }
+  });
+  const nativePromise = nativeBinding.request(requestId === undefined ? options : { ...options, requestId });
   const pending = abortHandler ? Promise.race([nativePromise, abortHandler.promise]) : nativePromise;
 
   let payload: NativeResponse;
@@ -690,11 +1242,7 @@ async function dispatchRequest(
       throw error;
     }
 
-    if (error instanceof RequestError) {
-      throw error;
-    }
-
-    throw new RequestError(String(error));
+    throw toRequestError(error);
   } finally {
     abortHandler?.cleanup();
   }
@@ -726,30 +1274,30 @@ export async function fetch(input: string | URL, init?: WreqRequestInit): Promis
   const headerTuples = headers.toTuples();
   const hasHeaders = headerTuples.length > 0;
 
-  const requestOptions: RequestOptions = {
+  if (config.proxy !== undefined && config.proxyPool !== undefined) {
+    throw new RequestError("Provide either `proxy` or `proxyPool`, not both.");
+  }
+
+  const proxyPool = config.proxyPool !== undefined ? toProxyPool(config.proxyPool) : undefined;
+
+  const requestOptions: NativeRequestOptions = {
     url,
     method,
     ...(config.browser && { browser: config.browser }),
+    ...(config.os !== undefined && { os: config.os }),
+    ...(config.skipHttp2 !== undefined && { skipHttp2: config.skipHttp2 }),
+    ...(config.skipHeaders !== undefined && { skipHeaders: config.skipHeaders }),
     ...(hasHeaders && { headers: headerTuples }),
     ...(body !== undefined && { body }),
-    ...(config.proxy !== undefined && { proxy: config.proxy }),
+    ...(config.proxy !== undefined && { proxy: toNativeProxy(config.proxy) }),
+    ...(proxyPool !== undefined && { proxyPool: proxyPool.nativeHandle }),
     ...(config.timeout !== undefined && { timeout: config.timeout }),
     ...(config.disableDefaultHeaders !== undefined && { disableDefaultHeaders: config.disableDefaultHeaders }),
-    sessionId: sessionContext.sessionId,
-    ephemeral: sessionContext.dropAfterRequest,
+    ...(config.cache !== undefined && { cache: config.cache }),
+    ...(sessionContext.sessionId !== undefined && { sessionId: sessionContext.sessionId }),
   };
 
-  try {
-    return await dispatchRequest(requestOptions, url, config.signal ?? null);
-  } finally {
-    if (sessionContext.dropAfterRequest) {
-      try {
-        nativeBinding.dropSession(sessionContext.sessionId);
-      } catch {
-        // ignore cleanup errors for ephemeral sessions
-      }
-    }
-  }
+  return dispatchRequest(requestOptions, url, config.signal ?? null);
 }
 
 export async function createSession(options?: CreateSessionOptions): Promise<Session> {
@@ -757,21 +1305,232 @@ export async function createSession(options?: CreateSessionOptions): Promise<Ses
 
   validateBrowserProfile(defaults.browser);
 
+  const headerTuples = new Headers(options?.headers).toTuples();
+  const proxy = defaults.proxy !== undefined ? toNativeProxy(defaults.proxy) : undefined;
+  const proxyResolver = options?.proxyResolver !== undefined ? toNativeProxyResolver(options.proxyResolver) : undefined;
+
   let createdId: string;
 
   try {
     createdId = nativeBinding.createSession({
       sessionId,
       browser: defaults.browser,
-      ...(defaults.proxy !== undefined && { proxy: defaults.proxy }),
+      ...(defaults.os !== undefined && { os: defaults.os }),
+      ...(defaults.skipHttp2 !== undefined && { skipHttp2: defaults.skipHttp2 }),
+      ...(defaults.skipHeaders !== undefined && { skipHeaders: defaults.skipHeaders }),
+      ...(proxy !== undefined && { proxy }),
+      ...(defaults.proxyPool !== undefined && { proxyPool: defaults.proxyPool.nativeHandle }),
+      ...(proxyResolver !== undefined && { proxyResolver }),
+      ...(options?.proxyResolverCacheTtl !== undefined && { proxyResolverCacheTtl: options.proxyResolverCacheTtl }),
+      ...(options?.connectionPool !== undefined && { connectionPool: options.connectionPool }),
+      ...(options?.rateLimits !== undefined && { rateLimits: options.rateLimits }),
+      ...(options?.cache !== undefined && { cache: options.cache }),
+      ...(options?.recorder !== undefined && { recorder: toHarRecorder(options.recorder).nativeHandle }),
+      ...(options?.cassette !== undefined && { cassette: toCassette(options.cassette).nativeHandle }),
+      ...(options?.beforeRequest !== undefined && { beforeRequest: toNativeBeforeRequestHook(options.beforeRequest) }),
+      ...(options?.afterResponse !== undefined && { afterResponse: toNativeAfterResponseHook(options.afterResponse) }),
+      ...(options?.neverExpire !== undefined && { neverExpire: options.neverExpire }),
+      ...(headerTuples.length > 0 && { headers: headerTuples }),
     });
   } catch (error) {
-    throw new RequestError(String(error));
+    throw toRequestError(error);
   }
 
   return new Session(createdId, defaults);
 }
 
+/**
+ * Recreate a session from {@link Session.snapshot} output. Cookies that expired since the
+ * snapshot was taken are dropped. A live session with the same id is replaced.
+ *
+ * @param snapshot - Snapshot JSON
+ * @param options - Restore under a different session id
+ *
+ * @example
+ * ```typescript
+ * const saved = session.snapshot();
+ * // ...later, possibly in another process
+ * const restored = await restoreSession(saved);
+ * ```
+ */
+export async function restoreSession(snapshot: string, options?: RestoreSessionOptions): Promise<Session> {
+  let restored: NativeRestoredSession;
+
+  try {
+    restored = nativeBinding.restoreSession(snapshot, options?.sessionId);
+  } catch (error) {
+    throw toRequestError(error);
+  }
+
+  const defaults: SessionDefaults = {
+    browser: restored.browser,
+    os: restored.os,
+    skipHttp2: restored.skipHttp2,
+    skipHeaders: restored.skipHeaders,
+    ...(restored.proxy !== null && { proxy: restored.proxy }),
+    ...(restored.proxyPool !== undefined && { proxyPool: ProxyPool.fromNative(restored.proxyPool) }),
+    ...(restored.timeout !== undefined && { timeout: restored.timeout }),
+  };
+
+  return new Session(restored.sessionId, defaults);
+}
+
+/**
+ * Restore a session from a file written by {@link Session.save}.
+ *
+ * @param path - Snapshot file
+ * @param options - Restore under a different session id
+ */
+export async function loadSession(path: string, options?: RestoreSessionOptions): Promise<Session> {
+  return restoreSession(await readFile(path, "utf8"), options);
+}
+
+/**
+ * List every live native session with its configuration and usage, oldest first.
+ * Looking at a session does not reset its idle timeout.
+ *
+ * @example
+ * ```typescript
+ * import { listSessions } from 'wreq-js';
+ *
+ * for (const info of listSessions()) {
+ *   console.log(info.sessionId, info.browser, info.proxy, info.requestCount, info.cookieCount);
+ * }
+ * ```
+ */
+export function listSessions(): SessionInfo[] {
+  return nativeBinding.listSessions();
+}
+
+/**
+ * Configuration and usage of one native session, or `null` if it does not exist.
+ *
+ * @param session - Session instance or session id
+ */
+export function getSessionInfo(session: SessionHandle | string): SessionInfo | null {
+  const sessionId = typeof session === "string" ? session : session.id;
+  return nativeBinding.getSessionInfo(sessionId);
+}
+
+/**
+ * Connections per origin as a session saw them, most recently used origin first. This is not
+ * the connection pool's state, which wreq does not expose. It is rebuilt from the local
+ * addresses responses arrive on: a request shows up once its response starts arriving, and a
+ * connection the server closes is not seen, so an idle one is still counted until the pool's
+ * limits would have dropped it. Reconfiguring or clearing the session starts over.
+ *
+ * @param session - Session instance or session id
+ */
+export function observedConnections(session: SessionHandle | string): ObservedConnections[] {
+  const sessionId = typeof session === "string" ? session : session.id;
+  try {
+    return nativeBinding.observedConnections(sessionId);
+  } catch (error) {
+    throw toRequestError(error);
+  }
+}
+
+/**
+ * Configure how long native sessions live, how many may exist at once, and get notified
+ * when one is evicted. A session used after eviction fails with a {@link RequestError}
+ * instead of silently starting over with an empty cookie jar.
+ *
+ * @param options - Limits to change and the eviction callback
+ *
+ * @example
+ * ```typescript
+ * import { configureSessions } from 'wreq-js';
+ *
+ * configureSessions({
+ *   timeToLive: 60 * 60_000,
+ *   maxSessions: 1000,
+ *   onEvict: ({ sessionId, reason }) => console.warn(`Lost session ${sessionId} (${reason})`),
+ * });
+ * ```
+ */
+export function configureSessions(options: SessionManagerOptions): void {
+  for (const key of ["timeToLive", "timeToIdle", "maxSessions"] as const) {
+    const value = options[key];
+    if (value !== undefined && value !== null && (!Number.isFinite(value) || value < 0)) {
+      throw new RequestError(`${key} must be a non-negative number or null`);
+    }
+  }
+
+  try {
+    nativeBinding.configureSessions(options);
+  } catch (error) {
+    throw new RequestError(String(error));
+  }
+}
+
+/**
+ * Limit the requests made to each host, counted separately per host across all sessions and plain
+ * {@link fetch} calls. Requests over a limit wait in line; the wait counts against their timeout.
+ * Pass `null` to remove the limits. Requests already waiting keep the old limits.
+ *
+ * @example
+ * ```typescript
+ * import { configureHostLimits } from 'wreq-js';
+ *
+ * configureHostLimits({ maxConcurrent: 4, requestsPerSecond: 2 });
+ * ```
+ */
+export function configureHostLimits(limits: RateLimitOptions | null): void {
+  try {
+    nativeBinding.configureHostLimits(limits);
+  } catch (error) {
+    throw new RequestError(String(error));
+  }
+}
+
+/**
+ * Forward native log events, batched, to a function or append them to a file. The level and
+ * target filters can be changed at any time; events filtered out cost next to nothing.
+ * Events that arrive faster than they can be delivered are dropped and counted.
+ *
+ * @param options - Filters to change and where events go
+ *
+ * @example
+ * ```typescript
+ * import { configureLogging } from 'wreq-js';
+ *
+ * configureLogging({ level: "debug", file: "./wreq.log" });
+ * // Later
+ * configureLogging({ file: null });
+ * ```
+ */
+export function configureLogging(options: LoggingOptions): void {
+  const { logger, ...rest } = options;
+  if (logger && options.file) {
+    throw new RequestError("configureLogging accepts either logger or file, not both");
+  }
+  for (const key of ["batchSize", "flushInterval"] as const) {
+    const value = options[key];
+    if (value !== undefined && (!Number.isFinite(value) || value < 0)) {
+      throw new RequestError(`${key} must be a non-negative number`);
+    }
+  }
+
+  try {
+    nativeBinding.configureLogging({
+      ...rest,
+      ...(logger !== undefined && {
+        logger: logger
+          ? (records: LogRecord[]) => {
+              try {
+                logger(records);
+              } catch {
+                // Nowhere to report it; an uncaught error here would crash the process
+              }
+            }
+          : null,
+      }),
+    });
+  } catch (error) {
+    throw new RequestError(String(error));
+  }
+}
+
 export async function withSession<T>(
   fn: (session: Session) => Promise<T> | T,
   options?: CreateSessionOptions,
@@ -812,6 +1571,18 @@ export async function request(options: RequestOptions): Promise<Response> {
     init.browser = rest.browser;
   }
 
+  if (rest.os !== undefined) {
+    init.os = rest.os;
+  }
+
+  if (rest.skipHttp2 !== undefined) {
+    init.skipHttp2 = rest.skipHttp2;
+  }
+
+  if (rest.skipHeaders !== undefined) {
+    init.skipHeaders = rest.skipHeaders;
+  }
+
   if (rest.proxy !== undefined) {
     init.proxy = rest.proxy;
   }
@@ -828,13 +1599,18 @@ export async function request(options: RequestOptions): Promise<Response> {
     init.disableDefaultHeaders = rest.disableDefaultHeaders;
   }
 
+  if (rest.cache !== undefined) {
+    init.cache = rest.cache;
+  }
+
   return fetch(url, init);
 }
 
 /**
  * Get list of available browser profiles
  *
- * @returns Array of browser profile names
+ * @param options - Pass `{ details: true }` to get family, version, platform and engine metadata
+ * @returns Array of browser profile names, or {@link ProfileInfo} entries with `details`
  *
  * @example
  * ```typescript
@@ -842,14 +1618,420 @@ export async function request(options: RequestOptions): Promise<Response> {
  *
  * const profiles = getProfiles();
  * console.log(profiles); // ['chrome_120', 'chrome_131', 'firefox', ...]
+ *
+ * const details = getProfiles({ details: true });
+ * console.log(details[0]); // { name: 'chrome_100', family: 'chrome', version: '100', platform: 'desktop', ... }
  * ```
  */
-export function getProfiles(): BrowserProfile[] {
-  if (!cachedProfiles) {
-    cachedProfiles = nativeBinding.getProfiles() as BrowserProfile[];
+export function getProfiles(): ProfileName[];
+export function getProfiles(options: { details: true }): ProfileInfo[];
+export function getProfiles(options?: { details?: boolean }): ProfileName[] | ProfileInfo[] {
+  if (!cachedProfiles || !cachedProfileNames) {
+    cachedProfiles = nativeBinding.getProfiles();
+    cachedProfileNames = cachedProfiles.map((profile) => profile.name);
+  }
+
+  if (options?.details) {
+    return cachedProfiles.map((profile) => ({ ...profile }));
+  }
+
+  return cachedProfileNames;
+}
+
+function compareVersions(a: string, b: string): number {
+  const left = a.split(".").map(Number);
+  const right = b.split(".").map(Number);
+
+  for (let i = 0; i < Math.max(left.length, right.length); i++) {
+    const diff = (left[i] ?? 0) - (right[i] ?? 0);
+    if (diff !== 0) {
+      return diff;
+    }
   }
 
-  return cachedProfiles;
+  return 0;
+}
+
+function matchesQuery(profile: ProfileInfo, query: ProfileQuery): boolean {
+  return (
+    (query.family === undefined || profile.family === query.family) &&
+    (query.platform === undefined || profile.platform === query.platform) &&
+    (query.engine === undefined || profile.engine === query.engine) &&
+    (query.private === undefined || profile.private === query.private)
+  );
+}
+
+/**
+ * Find profiles matching a query, oldest version first.
+ *
+ * @example
+ * ```typescript
+ * import { findProfiles } from 'wreq-js';
+ *
+ * findProfiles({ family: 'safari', platform: 'ipad' }); // [{ name: 'safari_ipad_18', ... }, ...]
+ * ```
+ */
+export function findProfiles(query: ProfileQuery = {}): ProfileInfo[] {
+  return getProfiles({ details: true })
+    .filter((profile) => matchesQuery(profile, query))
+    .sort((a, b) => compareVersions(a.version ?? "0", b.version ?? "0"));
+}
+
+/**
+ * Name of the newest profile matching a query, or `undefined` when nothing matches.
+ * Private-mode profiles are excluded unless `private: true` is requested.
+ *
+ * @example
+ * ```typescript
+ * import { latestProfile } from 'wreq-js';
+ *
+ * latestProfile({ family: 'firefox', platform: 'mobile' }); // 'firefox_android_135'
+ * ```
+ */
+export function latestProfile(query: ProfileQuery = {}): ProfileName | undefined {
+  const matches = findProfiles({ private: false, ...query });
+  return matches[matches.length - 1]?.name;
+}
+
+/**
+ * Weighted random profile picker backed by the native module.
+ * Create one with {@link createProfileSelector}.
+ */
+export class ProfileSelector implements ProfileSelectorHandle {
+  private readonly handle: NativeProfileSelector;
+
+  constructor(options: ProfileSelectorOptions) {
+    if (options.seed !== undefined && (!Number.isSafeInteger(options.seed) || options.seed < 0)) {
+      throw new RequestError("seed must be a non-negative safe integer");
+    }
+
+    try {
+      this.handle = nativeBinding.createProfileSelector(options);
+    } catch (error) {
+      throw new RequestError(String(error));
+    }
+  }
+
+  /**
+   * Pick the next profile according to the configured weights.
+   */
+  pick(): ProfileName {
+    return nativeBinding.pickProfile(this.handle);
+  }
+}
+
+/**
+ * Create a weighted profile selector. Weights can target individual profiles
+ * or whole families; pass a `seed` for a reproducible sequence.
+ *
+ * @example
+ * ```typescript
+ * import { createProfileSelector, createSession } from 'wreq-js';
+ *
+ * const selector = createProfileSelector({ weights: { chrome: 7, firefox: 2, safari: 1 }, seed: 1 });
+ * const session = await createSession({ profileSelector: selector });
+ * console.log(session.browser);
+ * ```
+ */
+export function createProfileSelector(options: ProfileSelectorOptions): ProfileSelector {
+  return new ProfileSelector(options);
+}
+
+/**
+ * Set of proxies shared by any number of requests and sessions, backed by the native module.
+ * Create one with {@link createProxyPool}.
+ */
+export class ProxyPool implements ProxyPoolHandle {
+  /** @internal */
+  readonly nativeHandle: NativeProxyPool;
+
+  constructor(options: ProxyPoolOptions) {
+    this.nativeHandle = ProxyPool.createNative(options);
+  }
+
+  /** @internal */
+  static fromNative(handle: NativeProxyPool): ProxyPool {
+    const pool = Object.create(ProxyPool.prototype) as { nativeHandle: NativeProxyPool };
+    pool.nativeHandle = handle;
+    return pool as ProxyPool;
+  }
+
+  private static createNative(options: ProxyPoolOptions): NativeProxyPool {
+    if (!Array.isArray(options.proxies) || options.proxies.length === 0) {
+      throw new RequestError("proxies must be a non-empty array of proxy URLs");
+    }
+
+    if (options.maxFailures !== undefined && (!Number.isSafeInteger(options.maxFailures) || options.maxFailures < 1)) {
+      throw new RequestError("maxFailures must be a positive integer");
+    }
+
+    try {
+      return nativeBinding.createProxyPool(options);
+    } catch (error) {
+      throw new RequestError(String(error));
+    }
+  }
+
+  /**
+   * Health and usage counters of every proxy in the pool, in the order they were given.
+   */
+  status(): ProxyStatus[] {
+    return nativeBinding.proxyPoolStatus(this.nativeHandle);
+  }
+}
+
+/**
+ * Records requests and their responses, for sessions given it as `recorder` or for every request
+ * with `setGlobalRecorder()`, and exports them as a HAR 1.2 log.
+ */
+export class HarRecorder implements HarRecorderHandle {
+  /** @internal */
+  readonly nativeHandle: NativeHarRecorder;
+
+  constructor(options?: HarRecorderOptions) {
+    try {
+      this.nativeHandle = nativeBinding.createHarRecorder(options);
+    } catch (error) {
+      throw new RequestError(String(error));
+    }
+  }
+
+  /**
+   * Number of entries recorded so far. A request that followed redirects has one per redirect.
+   */
+  get entryCount(): number {
+    return nativeBinding.harRecorderCount(this.nativeHandle);
+  }
+
+  /**
+   * The recorded entries as a HAR 1.2 log, in the order the requests finished.
+   */
+  toHar(): Har {
+    try {
+      return JSON.parse(nativeBinding.harRecorderExport(this.nativeHandle)) as Har;
+    } catch (error) {
+      throw new RequestError(String(error));
+    }
+  }
+
+  /**
+   * Write the log to a `.har` file, which browser dev tools and HAR viewers can open.
+   */
+  async save(path: string): Promise<void> {
+    await writeFile(path, nativeBinding.harRecorderExport(this.nativeHandle), { encoding: "utf8", mode: 0o600 });
+  }
+
+  clear(): void {
+    nativeBinding.harRecorderClear(this.nativeHandle);
+  }
+}
+
+function toHarRecorder(handle: HarRecorderHandle): HarRecorder {
+  if (!(handle instanceof HarRecorder)) {
+    throw new RequestError("`recorder` must be created via createHarRecorder()");
+  }
+
+  return handle;
+}
+
+/**
+ * Recorded requests and WebSocket conversations, for sessions and WebSockets given it as `cassette` or
+ * for everything with `setGlobalCassette()`. Open one with `openCassette()`.
+ */
+export class Cassette implements CassetteHandle {
+  /** @internal */
+  readonly nativeHandle: NativeCassette;
+  readonly path: string;
+  readonly mode: CassetteMode;
+
+  /** @internal */
+  constructor(options: CassetteOptions, contents?: string) {
+    const { path, ...rules } = options;
+    this.path = path;
+    this.mode = options.mode;
+
+    try {
+      this.nativeHandle = nativeBinding.createCassette({ ...rules, ...(contents !== undefined && { contents }) });
+    } catch (error) {
+      throw new RequestError(String(error));
+    }
+  }
+
+  /**
+   * Number of interactions in the cassette: recorded so far, or loaded to replay.
+   */
+  get interactionCount(): number {
+    return nativeBinding.cassetteCount(this.nativeHandle);
+  }
+
+  /**
+   * Write the interactions to `path`, or to the cassette's own path.
+   */
+  async save(path: string = this.path): Promise<void> {
+    await writeFile(path, nativeBinding.cassetteExport(this.nativeHandle), { encoding: "utf8", mode: 0o600 });
+  }
+}
+
+function toCassette(handle: CassetteHandle): Cassette {
+  if (!(handle instanceof Cassette)) {
+    throw new RequestError("`cassette` must be created via openCassette()");
+  }
+
+  return handle;
+}
+
+function toProxyPool(handle: ProxyPoolHandle): ProxyPool {
+  if (!(handle instanceof ProxyPool)) {
+    throw new RequestError("`proxyPool` must be created via createProxyPool()");
+  }
+
+  return handle;
+}
+
+/**
+ * Create a pool of proxies that requests and sessions rotate through. Failing proxies
+ * are skipped for the configured cooldown; `response.proxy` reports the one that was used.
+ *
+ * @example
+ * ```typescript
+ * import { createProxyPool, createSession } from 'wreq-js';
+ *
+ * const pool = createProxyPool({
+ *   proxies: ['http://proxy-1.example.com:8080', 'http://proxy-2.example.com:8080'],
+ *   strategy: 'least-failures',
+ * });
+ * const session = await createSession({ proxyPool: pool });
+ * const response = await session.fetch('https://example.com');
+ * console.log(response.proxy, pool.status());
+ * ```
+ */
+export function createProxyPool(options: ProxyPoolOptions): ProxyPool {
+  return new ProxyPool(options);
+}
+
+/**
+ * Create a recorder that keeps requests and responses for export as a HAR file.
+ *
+ * @example
+ * ```typescript
+ * const recorder = createHarRecorder({ maxBodySize: 64 * 1024 });
+ * const session = await createSession({ recorder });
+ * await session.fetch('https://example.com');
+ * await recorder.save('./example.har');
+ * ```
+ */
+export function createHarRecorder(options?: HarRecorderOptions): HarRecorder {
+  return new HarRecorder(options);
+}
+
+/**
+ * Record every request, through a session or not, with `recorder`; `null` stops. Requests of a
+ * session with the same recorder are recorded once.
+ */
+export function setGlobalRecorder(recorder: HarRecorderHandle | null): void {
+  nativeBinding.setGlobalRecorder(recorder === null ? null : toHarRecorder(recorder).nativeHandle);
+}
+
+/**
+ * Open a cassette to record requests to, or to replay them from without a network. Replaying reads
+ * `path` now; recording writes it when {@link Cassette.save} is called.
+ *
+ * @example
+ * ```typescript
+ * const mode = process.env.RECORD ? 'record' : 'replay';
+ * const cassette = await openCassette({ path: './fixtures/api.json', mode, ignoreQueryParams: ['ts'] });
+ * const session = await createSession({ cassette });
+ * await session.fetch('https://api.example.com/items');
+ * if (mode === 'record') await cassette.save();
+ * ```
+ */
+export async function openCassette(options: CassetteOptions): Promise<Cassette> {
+  let contents: string | undefined;
+  if (options.mode === "replay") {
+    try {
+      contents = await readFile(options.path, "utf8");
+    } catch (error) {
+      throw new RequestError(String(error));
+    }
+  }
+
+  return new Cassette(options, contents);
+}
+
+/**
+ * Record or replay every request and WebSocket, through a session or not, that has no cassette of its
+ * own; `null` goes back to the network.
+ */
+export function setGlobalCassette(cassette: CassetteHandle | null): void {
+  nativeBinding.setGlobalCassette(cassette === null ? null : toCassette(cassette).nativeHandle);
+}
+
+/**
+ * Register a custom emulation profile that can then be used as `browser`
+ * for requests, sessions and WebSockets. Registering an existing custom name
+ * replaces it for new requests and sessions; sessions that already use it keep the
+ * old definition. Built-in profile names are reserved.
+ *
+ * @param name - Name to register the profile under
+ * @param definition - Profile definition, as an object or a JSON string
+ *
+ * @example
+ * ```typescript
+ * import { registerProfile, fetch } from 'wreq-js';
+ *
+ * registerProfile('my_chrome', {
+ *   extends: 'chrome_142',
+ *   headers: { 'User-Agent': 'MyAgent/1.0' },
+ * });
+ *
+ * await fetch('https://example.com', { browser: 'my_chrome' });
+ * ```
+ */
+export function registerProfile(name: string, definition: CustomProfileDefinition | string): void {
+  const json = typeof definition === "string" ? definition : JSON.stringify(definition);
+
+  try {
+    nativeBinding.registerProfile(name, json);
+  } catch (error) {
+    throw new RequestError(String(error));
+  }
+
+  cachedProfiles = undefined;
+  cachedProfileNames = undefined;
+}
+
+type NativeProfileError = Error & { code?: string; profile?: string; suggestions?: string[] };
+
+/**
+ * Resolve a profile name or `_latest` alias to the concrete profile it stands for.
+ * Without a name, returns the default profile (the newest Chrome).
+ *
+ * @throws {UnknownProfileError} When the name matches no profile; `suggestions` lists close matches
+ *
+ * @example
+ * ```typescript
+ * import { resolveProfile } from 'wreq-js';
+ *
+ * resolveProfile('safari_ios_latest'); // 'safari_ios_26'
+ * resolveProfile(); // 'chrome_142'
+ * ```
+ */
+export function resolveProfile(name?: ProfileName): ProfileName {
+  try {
+    return nativeBinding.resolveProfile(name);
+  } catch (error) {
+    const nativeError = error as NativeProfileError;
+
+    if (nativeError?.code === "ERR_UNKNOWN_PROFILE") {
+      throw new UnknownProfileError(
+        nativeError.message,
+        nativeError.profile ?? String(name),
+        nativeError.suggestions ?? [],
+      );
+    }
+
+    throw new RequestError(String(error));
+  }
 }
 
 /**
@@ -970,20 +2152,20 @@ export async function websocket(options: WebSocketOptions): Promise<WebSocket> {
     throw new RequestError("onMessage callback is required");
   }
 
-  if (options.browser) {
-    const profiles = getProfiles();
+  validateBrowserProfile(options.browser);
 
-    if (!profiles.includes(options.browser)) {
-      throw new RequestError(`Invalid browser profile: ${options.browser}. Available profiles: ${profiles.join(", ")}`);
-    }
-  }
+  const proxy = options.proxy !== undefined ? toNativeProxy(options.proxy) : undefined;
 
   try {
     const connection = await nativeBinding.websocketConnect({
       url: options.url,
-      browser: options.browser || DEFAULT_BROWSER,
+      ...(options.browser && { browser: options.browser }),
+      ...(options.os !== undefined && { os: options.os }),
+      ...(options.skipHttp2 !== undefined && { skipHttp2: options.skipHttp2 }),
+      ...(options.skipHeaders !== undefined && { skipHeaders: options.skipHeaders }),
       headers: options.headers || {},
-      ...(options.proxy !== undefined && { proxy: options.proxy }),
+      ...(proxy !== undefined && { proxy }),
+      ...(options.cassette !== undefined && { cassette: toCassette(options.cassette).nativeHandle }),
       onMessage: options.onMessage,
       ...(options.onClose !== undefined && { onClose: options.onClose }),
       ...(options.onError !== undefined && { onError: options.onError }),
@@ -991,24 +2173,77 @@ export async function websocket(options: WebSocketOptions): Promise<WebSocket> {
 
     return new WebSocket(connection);
   } catch (error) {
-    throw new RequestError(String(error));
+    throw toRequestError(error);
   }
 }
 
 export type {
+  AfterResponseHook,
+  BeforeRequestHook,
+  BeforeRequestResult,
   BodyInit,
   BrowserProfile,
+  CacheStatus,
+  CassetteHandle,
+  CassetteMode,
+  CassetteOptions,
+  ConnectionPoolOptions,
   CookieMode,
   CreateSessionOptions,
+  CustomProfileDefinition,
+  EmulationOS,
+  Har,
+  HarCookie,
+  HarEntry,
+  HarNameValue,
+  HarRecorderHandle,
+  HarRecorderOptions,
   HeadersInit,
+  HookRequest,
+  HookResponse,
+  HookResponseInit,
+  HttpCacheOptions,
   HttpMethod,
+  LoggingOptions,
+  LogLevel,
+  LogRecord,
+  ObservedConnections,
+  ProfileAlias,
+  ProfileInfo,
+  ProfileName,
+  ProfileQuery,
+  ProfileSelectorHandle,
+  ProfileSelectorOptions,
+  ProxyInit,
+  ProxyMode,
+  ProxyOptions,
+  ProxyPoolHandle,
+  ProxyPoolOptions,
+  ProxyResolver,
+  ProxyRotationStrategy,
+  ProxyStatus,
+  RateLimitOptions,
+  ReconfigureSessionOptions,
+  RequestCacheMode,
+  RequestErrorCode,
+  RequestErrorDetails,
+  RequestErrorPhase,
   RequestInit,
   RequestOptions,
+  RestoreSessionOptions,
+  SessionEvictionEvent,
+  SessionEvictionReason,
   SessionHandle,
+  SessionInfo,
+  SessionManagerOptions,
+  SessionResetMode,
+  SessionSnapshot,
+  SnapshotOptions,
+  SessionSnapshotCookie,
   WebSocketOptions,
 } from "./types";
 
-export { RequestError };
+export { ProxyConnectError, RequestError, UnknownProfileError };
 
 export default {
   fetch,
@@ -1016,8 +2251,30 @@ export default {
   get,
   post,
   getProfiles,
+  findProfiles,
+  latestProfile,
+  resolveProfile,
+  registerProfile,
+  createProfileSelector,
+  ProfileSelector,
+  createProxyPool,
+  ProxyPool,
+  createHarRecorder,
+  setGlobalRecorder,
+  HarRecorder,
+  openCassette,
+  setGlobalCassette,
+  Cassette,
   createSession,
   withSession,
+  restoreSession,
+  loadSession,
+  configureSessions,
+  configureHostLimits,
+  configureLogging,
+  listSessions,
+  getSessionInfo,
+  observedConnections,
   websocket,
   WebSocket,
   Headers,